@@ -1,24 +1,40 @@
 /// This defines the possible states of the tic-tac-toe board
-#[derive(Debug, Clone)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SquareType {
+    #[default]
     B, // Blank square
-    O, 
-    X, 
+    O,
+    X,
+    Blocked, // Square that can't be played in
 }
 
-/// For `SquareType::B` render a space. For the others, render the corresponding letter
+impl SquareType {
+    /// The player whose own pieces these are. `None` for blank and blocked squares.
+    pub fn owner(&self) -> Option<Player> {
+        match self {
+            Self::B | Self::Blocked => None,
+            Self::O => Some(Player::O),
+            Self::X => Some(Player::X),
+        }
+    }
+}
+
+/// For `SquareType::B` render a space and for `SquareType::Blocked` a '#'. For the others, render
+/// the corresponding letter
 impl std::fmt::Display for SquareType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::B => write!(f, " "),
             Self::O => write!(f, "O"),
             Self::X => write!(f, "X"),
+            Self::Blocked => write!(f, "#"),
         }
     }
 }
 
 /// This helps with configuring the user's preference for moving first/second and playing
 /// with X/O pieces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Opponent {
     User,
     Computer,
@@ -33,7 +49,8 @@ impl Opponent {
     }
 }
 
-/// This along with `Opponent` helps define what pieces the user/computer is playing wirh 
+/// This along with `Opponent` helps define what pieces the user/computer is playing wirh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Player {
     X,
     O,
@@ -58,6 +75,15 @@ impl Player {
     }
 }
 
+impl std::fmt::Display for Player {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::X => write!(f, "X"),
+            Self::O => write!(f, "O"),
+        }
+    }
+}
+
 /// The x, y coordinates of a square on a board
 #[derive(Default, Debug, PartialEq, Eq, Hash, Copy, Clone, PartialOrd, Ord)]
 pub struct Point {
@@ -75,3 +101,35 @@ impl Point {
     // Define an associated constant to help eliminate "magic numbers"
     pub const NUM_ARGUMENTS: usize = 2;
 }
+
+/// A point written as "x,y"
+impl std::str::FromStr for Point {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |coordinate: &str| coordinate.trim().parse().map_err(|e| format!("{e}"));
+        match s.split_once(',') {
+            Some((x, y)) => Ok(Point {
+                x: parse(x)?,
+                y: parse(y)?,
+            }),
+            None => Err(format!("expected a point written as \"x,y\". Got {s:?}")),
+        }
+    }
+}
+
+/// The rules a `Board` is played with. The default is classic tic-tac-toe.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    /// Misère play: completing a line loses the game instead of winning it
+    pub misere: bool,
+    /// Wild tic-tac-toe: on every turn the player to move chooses whether to place an X or an O,
+    /// and whoever completes a line of either wins
+    pub wild: bool,
+    /// Pieces drop to the lowest blank square of the column they are played in, as in Connect
+    /// Four. The bottom row is the one with the largest `x`.
+    pub gravity: bool,
+    /// The board wraps around at its edges, so every broken diagonal (one that runs off one edge
+    /// and continues from the opposite edge) is a line too
+    pub torus: bool,
+}
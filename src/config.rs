@@ -1,17 +1,33 @@
 // Functionality for configuration of the tic-tac-toe game
+use crate::board_info::{Player, Point, Rules, SquareType};
+use crate::morris::Morris;
+use crate::notakto::Notakto;
+use crate::order_chaos::OrderAndChaos;
+use crate::quantum::Quantum;
+use crate::qubic::Cube;
+use crate::tablebase::Tablebase;
+use crate::tic_tac_toe_board::Board;
+use crate::ultimate::Ultimate;
+
 #[derive(Debug)]
 pub enum Error {
     PlayerPieceType(String),
     FirstOrSecond(String),
     NoArgument(String),
+    UnknownOption(String),
+    OptionValue(String),
+    Position(String),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
-            Error::FirstOrSecond(e) | Error::PlayerPieceType(e) | Error::NoArgument(e) => {
-                write!(f, "{e}")
-            }
+            Error::FirstOrSecond(e)
+            | Error::PlayerPieceType(e)
+            | Error::NoArgument(e)
+            | Error::UnknownOption(e)
+            | Error::OptionValue(e)
+            | Error::Position(e) => write!(f, "{e}"),
         }
     }
 }
@@ -22,16 +38,298 @@ impl std::error::Error for Error {}
 pub struct PlayerIsX(pub bool);
 pub struct MoveFirst(pub bool);
 
+/// Which algorithm the computer opponent uses to choose its moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineType {
+    AlphaBeta,
+    Mcts,
+    Tablebase,
+}
+
+impl std::str::FromStr for EngineType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "alpha-beta" => Ok(EngineType::AlphaBeta),
+            "mcts" => Ok(EngineType::Mcts),
+            "tablebase" => Ok(EngineType::Tablebase),
+            _ => Err("expected \"alpha-beta\", \"mcts\", or \"tablebase\"".to_string()),
+        }
+    }
+}
+
+/// A rule that changes how a game against the computer starts, to make up for its perfect play
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handicap {
+    /// The computer's first move has to be in a corner
+    Corner,
+    /// The user plays two moves in a row on their first turn
+    TwoMoves,
+    /// The computer passes on its first turn
+    Skip,
+    /// After the first move, the second player may swap sides and take that move as their own
+    Swap,
+}
+
+impl std::str::FromStr for Handicap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "corner" => Ok(Handicap::Corner),
+            "two-moves" => Ok(Handicap::TwoMoves),
+            "skip" => Ok(Handicap::Skip),
+            "swap" => Ok(Handicap::Swap),
+            _ => Err("expected \"corner\", \"two-moves\", \"skip\", or \"swap\"".to_string()),
+        }
+    }
+}
+
+/// Written the way it is given to `--handicap`
+impl std::fmt::Display for Handicap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Handicap::Corner => write!(f, "corner"),
+            Handicap::TwoMoves => write!(f, "two-moves"),
+            Handicap::Skip => write!(f, "skip"),
+            Handicap::Swap => write!(f, "swap"),
+        }
+    }
+}
+
+/// Which game is played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Standard,
+    /// Completing a line loses
+    Misere,
+    /// Either player may place either piece, and whoever completes a line wins
+    Wild,
+    /// Both players place X on several boards, and whoever kills the last board loses (see
+    /// `Notakto`)
+    Notakto,
+    /// A 3x3 grid of boards, where each move decides the board the opponent plays on next (see
+    /// `Ultimate`)
+    Ultimate,
+    /// Tic-tac-toe in a cube, with lines through its layers (see `Cube`)
+    Qubic,
+    /// Each player has three pieces, and once they are placed moves one of them to any blank
+    /// square (see `Morris`)
+    ThreePieces,
+    /// Three Men's Morris: as `ThreePieces`, but pieces only move to a neighbouring square
+    Morris,
+    /// Both sides place either piece. Order wins with five in a row and Chaos by filling the board
+    /// without one (see `OrderAndChaos`).
+    OrderAndChaos,
+    /// The first player writes odd numbers and the second even ones, and a line adding up to 15
+    /// wins (see `Numerical`)
+    Numerical,
+    /// Each move puts spooky marks in two squares, which collapse into classical marks once they
+    /// make a cycle (see `Quantum`)
+    Quantum,
+}
+
+impl Variant {
+    /// Whether the game isn't played on a single `Board`, so it has its own computer opponent
+    pub fn has_own_engine(&self) -> bool {
+        matches!(
+            self,
+            Variant::Notakto
+                | Variant::Ultimate
+                | Variant::Qubic
+                | Variant::ThreePieces
+                | Variant::Morris
+                | Variant::OrderAndChaos
+                | Variant::Numerical
+                | Variant::Quantum
+        )
+    }
+}
+
+impl std::str::FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(Variant::Standard),
+            "misere" => Ok(Variant::Misere),
+            "wild" => Ok(Variant::Wild),
+            "notakto" => Ok(Variant::Notakto),
+            "ultimate" => Ok(Variant::Ultimate),
+            "qubic" => Ok(Variant::Qubic),
+            "three-pieces" => Ok(Variant::ThreePieces),
+            "morris" => Ok(Variant::Morris),
+            "order-and-chaos" => Ok(Variant::OrderAndChaos),
+            "numerical" => Ok(Variant::Numerical),
+            "quantum" => Ok(Variant::Quantum),
+            _ => Err(
+                "expected \"standard\", \"misere\", \"wild\", \"notakto\", \"ultimate\", \"qubic\", \"three-pieces\", \"morris\", \"order-and-chaos\", \"numerical\", or \"quantum\""
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+/// What the program has been asked to do
+pub enum Command {
+    /// Play a game against the computer
+    Play(Config),
+    /// Solve every 3x3 position and write the resulting tablebase to a file
+    GenerateTablebase(std::path::PathBuf),
+    /// Search the first `plies` plies with the configured engine and save the moves it chose as an
+    /// opening book
+    BuildBook {
+        path: std::path::PathBuf,
+        plies: usize,
+        config: Config,
+    },
+    /// Count the games and positions of the board chosen by the options
+    Stats(Config),
+    /// Write the tree searched from `board` as a Graphviz DOT file
+    ExportDot {
+        path: std::path::PathBuf,
+        board: Board,
+        player: Player,
+        /// How many plies to look ahead. By default, to the end of the game.
+        depth: Option<u32>,
+        /// Whether to export every position within `depth` rather than the alpha-beta search tree
+        full_tree: bool,
+    },
+}
+
+impl Command {
+    /// Subcommands are recognised by their first argument. Anything else is the configuration of
+    /// a game (see `Config::build()`).
+    pub fn build(args: impl Iterator<Item = String>) -> Result<Self, Error> {
+        let args: Vec<String> = args.collect();
+        match args.get(1).map(String::as_str) {
+            Some("generate-tablebase") => match args.get(2) {
+                Some(path) => Ok(Self::GenerateTablebase(path.into())),
+                None => Err(Error::NoArgument(
+                    "No path given to write the tablebase to".to_string(),
+                )),
+            },
+            Some("build-book") => {
+                let mut args = args.into_iter().skip(2);
+                let path = args.next().ok_or_else(|| {
+                    Error::NoArgument("No path given to write the opening book to".to_string())
+                })?;
+                let plies = parse_option_value("plies", args.next())?;
+                // The pieces and turn order don't matter when building a book
+                let mut config = Config::with_defaults(PlayerIsX(true), MoveFirst(true));
+                config.parse_options(args)?;
+                if config.variant.has_own_engine() {
+                    return Err(Error::OptionValue(
+                        "--variant: opening books only cover games on a single square board"
+                            .to_string(),
+                    ));
+                }
+                Ok(Self::BuildBook {
+                    path: path.into(),
+                    plies,
+                    config,
+                })
+            }
+            Some("stats") => {
+                let mut config = Config::with_defaults(PlayerIsX(true), MoveFirst(true));
+                config.parse_options(args.into_iter().skip(2))?;
+                if config.variant.has_own_engine() {
+                    return Err(Error::OptionValue(
+                        "--variant: stats only counts games on a single square board".to_string(),
+                    ));
+                }
+                Ok(Self::Stats(config))
+            }
+            Some("export-dot") => Self::build_export_dot(args.into_iter().skip(2)),
+            _ => Config::build(args.into_iter()).map(Self::Play),
+        }
+    }
+
+    // export-dot [path] [position] [--depth <n>] [--full-tree]
+    fn build_export_dot(mut args: impl Iterator<Item = String>) -> Result<Self, Error> {
+        let path = args
+            .next()
+            .ok_or_else(|| Error::NoArgument("No path given to write the tree to".to_string()))?;
+        let board: Board = args
+            .next()
+            .ok_or_else(|| Error::NoArgument("No position given to search from".to_string()))?
+            .parse()
+            .map_err(|e| Error::Position(format!("Position: {e}")))?;
+
+        // The player with fewer pieces is to move. With as many of each, X is.
+        let count = |square_type| {
+            (0..board.size())
+                .flat_map(|x| (0..board.size()).map(move |y| Point { x, y }))
+                .filter(|point| board.square(point) == square_type)
+                .count()
+        };
+        let player = if count(SquareType::O) < count(SquareType::X) {
+            Player::O
+        } else {
+            Player::X
+        };
+
+        let (mut depth, mut full_tree) = (None, false);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--depth" => depth = Some(parse_option_value(&arg, args.next())?),
+                "--full-tree" => full_tree = true,
+                x => return Err(Error::UnknownOption(format!("Unknown option {x:?}"))),
+            }
+        }
+
+        Ok(Self::ExportDot {
+            path: path.into(),
+            board,
+            player,
+            depth,
+            full_tree,
+        })
+    }
+}
+
 pub struct Config {
     pub player_piece_type: PlayerIsX,
     pub first_or_second: MoveFirst,
+    pub board_size: usize,
+    pub variant: Variant,
+    /// How many boards a game of Notakto is played on
+    pub boards: usize,
+    /// How many plies ahead the engines of the games with their own rules search, if not their
+    /// default
+    pub depth: Option<u32>,
+    /// Whether pieces drop to the bottom of their column
+    pub gravity: bool,
+    /// Whether the board wraps around at its edges
+    pub torus: bool,
+    /// Squares no one can play in
+    pub blocked: Vec<Point>,
+    /// The handicaps the game starts with
+    pub handicaps: Vec<Handicap>,
+    /// How many games a match is played over
+    pub games: u32,
+    /// Whether the user and computer take turns playing X over a match, as well as moving first
+    pub alternate_pieces: bool,
+    pub engine: EngineType,
+    pub mcts_iterations: u32,
+    pub mcts_time_limit: Option<std::time::Duration>,
+    pub seed: Option<u64>,
+    pub threads: usize,
+    pub verbose: bool,
+    /// An opening book to play from before asking the engine
+    pub book: Option<std::path::PathBuf>,
 }
 
 #[rustfmt::skip]
 impl Config {
     pub const CLI_HELP_MESSAGE: &'static str =
         "Usage:\n\
-         tic-tac-toe [player_piece_type] [first_or_second]\n\
+         tic-tac-toe [player_piece_type] [first_or_second] [options]\n\
+         tic-tac-toe generate-tablebase [path]\n\
+         tic-tac-toe build-book [path] [plies] [options]\n\
+         tic-tac-toe stats [options]\n\
+         tic-tac-toe export-dot [path] [position] [--depth <n>] [--full-tree]\n\
          \n\
          player_piece_type\
          \n     \"x\" (or \"X\")     Play with X pieces\
@@ -40,7 +338,77 @@ impl Config {
          first_or_second\
          \n     \"1\"              Play first\
          \n     \"2\"              Play second\n\
+         \n\
+         options\
+         \n     --size <n>                   Play on an n by n board (default 3)\
+         \n     --variant <standard|misere|wild|notakto|ultimate|qubic|three-pieces|morris|\
+         \n                order-and-chaos|numerical|quantum>\
+         \n                                  Which game to play (default standard). In misere,\
+         \n                                  completing a line loses. In wild, either player may\
+         \n                                  place either piece and completing any line wins. In\
+         \n                                  notakto, both players place X on 3x3 boards and\
+         \n                                  whoever kills the last board loses. Ultimate is played\
+         \n                                  on a 3x3 grid of boards, and qubic in a cube with\
+         \n                                  sides of --size cells (default 4). In\
+         \n                                  three-pieces, each player has three pieces and then\
+         \n                                  moves them to any blank square, and in morris (Three\
+         \n                                  Men's Morris) only to a neighbouring one. In\
+         \n                                  order-and-chaos (on a 6x6 board unless --size is\
+         \n                                  given), both sides place either piece: Order, who\
+         \n                                  moves first, wins with five in a row, and Chaos wins\
+         \n                                  if the board fills up without one. In numerical, the\
+         \n                                  first player writes odd numbers and the second even\
+         \n                                  ones, and completing a line adding up to 15 wins. In\
+         \n                                  quantum, each move puts spooky marks in two squares,\
+         \n                                  which collapse into classical marks once they make a\
+         \n                                  cycle\
+         \n     --gravity                    Pieces drop to the lowest blank square of the column\
+         \n                                  they are played in, and moves name only the column\
+         \n     --torus                      The board wraps around at its edges, so diagonals that\
+         \n                                  run off one edge and continue from the other count\
+         \n     --blocked <x,y>              Block the square in row x, column y so no one can play\
+         \n                                  there. Can be given more than once\
+         \n     --handicap <corner|two-moves|skip|swap>\
+         \n                                  Give the user a head start: the computer opens in a\
+         \n                                  corner, the user moves twice on their first turn, or\
+         \n                                  the computer passes its first turn. With swap, the\
+         \n                                  second player may take the first move as their own\
+         \n                                  and swap sides. Can be given more than once\
+         \n     --games <n>                  Play a match of n games (default 1), ending early once\
+         \n                                  one side can't be caught. The first move alternates\
+         \n     --alternate-pieces           Alternate who plays X between the games of a match too\
+         \n     --boards <n>                 Boards in a game of notakto (default 3)\
+         \n     --depth <n>                  Plies the ultimate (default 6), qubic (default 4),\
+         \n                                  order-and-chaos (default 2), numerical (default 6) and\
+         \n                                  quantum (default 3) engines search ahead\
+         \n     --engine <alpha-beta|mcts|tablebase>\
+         \n                                  Computer opponent algorithm (default alpha-beta)\
+         \n     --iterations <n>             Monte Carlo Tree Search playouts per move (default 10000)\
+         \n     --time-ms <n>                Monte Carlo Tree Search time limit per move\
+         \n     --seed <n>                   Monte Carlo Tree Search random seed\
+         \n     --threads <n>                Alpha-beta search threads for build-book (default 1)\
+         \n     --book <path>                Play the first moves from an opening book\
+         \n     --verbose, --engine-stats    Print search statistics for every computer move\n\
+         \n\
+         generate-tablebase\
+         \n     Solve every 3x3 position and write the tablebase to [path]\n\
+         \n\
+         build-book\
+         \n     Search every position of the first [plies] plies with the engine chosen by [options]\
+         \n     and write the moves it chooses to the opening book [path]\n\
+         \n\
+         stats\
+         \n     Count every game and position of the board chosen by [options], and who wins them\n\
+         \n\
+         export-dot\
+         \n     Write the alpha-beta search tree of [position] (e.g. \"XOB/BXB/BBB\") to [path] as a\
+         \n     Graphviz DOT file, with the pruned moves drawn dashed. --full-tree writes every\
+         \n     position instead, and --depth <n> stops after n plies\n\
          \n";
+
+    const DEFAULT_BOARD_SIZE: usize = 3;
+    const DEFAULT_MCTS_ITERATIONS: u32 = 10_000;
+    const DEFAULT_NOTAKTO_BOARDS: usize = 3;
 }
 
 impl Config {
@@ -78,9 +446,351 @@ impl Config {
             return Err(Error::NoArgument("No first_or_second arg".to_string()));
         }
 
-        Ok(Config {
+        let mut config = Config::with_defaults(player_piece_type, first_or_second);
+        config.parse_options(args)?;
+        Ok(config)
+    }
+
+    fn with_defaults(player_piece_type: PlayerIsX, first_or_second: MoveFirst) -> Self {
+        Config {
             player_piece_type,
             first_or_second,
-        })
+            board_size: Self::DEFAULT_BOARD_SIZE,
+            variant: Variant::Standard,
+            boards: Self::DEFAULT_NOTAKTO_BOARDS,
+            depth: None,
+            gravity: false,
+            torus: false,
+            blocked: Vec::new(),
+            handicaps: Vec::new(),
+            games: 1,
+            alternate_pieces: false,
+            engine: EngineType::AlphaBeta,
+            mcts_iterations: Self::DEFAULT_MCTS_ITERATIONS,
+            mcts_time_limit: None,
+            seed: None,
+            threads: 1,
+            verbose: false,
+            book: None,
+        }
+    }
+
+    /// The rules the `Board` is played with
+    pub fn rules(&self) -> Rules {
+        Rules {
+            misere: self.variant == Variant::Misere,
+            wild: self.variant == Variant::Wild,
+            gravity: self.gravity,
+            torus: self.torus,
+        }
+    }
+
+    /// The board a game starts from: empty apart from the blocked squares
+    pub fn starting_board(&self) -> Board {
+        let mut board = Board::with_rules(self.board_size, self.rules());
+        for point in &self.blocked {
+            board
+                .block(point)
+                .expect("blocked squares are checked to be on the board and distinct");
+        }
+        board
+    }
+
+    // Everything after the positional arguments is an optional flag or "--name value" pair
+    fn parse_options(&mut self, mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+        let mut size_given = false;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--verbose" | "--engine-stats" => self.verbose = true,
+                "--gravity" => self.gravity = true,
+                "--torus" => self.torus = true,
+                "--blocked" => self.blocked.push(parse_option_value(&arg, args.next())?),
+                "--handicap" => self.handicaps.push(parse_option_value(&arg, args.next())?),
+                "--games" => self.games = parse_option_value(&arg, args.next())?,
+                "--alternate-pieces" => self.alternate_pieces = true,
+                "--size" => {
+                    self.board_size = parse_option_value(&arg, args.next())?;
+                    size_given = true;
+                }
+                "--variant" => self.variant = parse_option_value(&arg, args.next())?,
+                "--boards" => self.boards = parse_option_value(&arg, args.next())?,
+                "--depth" => self.depth = Some(parse_option_value(&arg, args.next())?),
+                "--engine" => self.engine = parse_option_value(&arg, args.next())?,
+                "--iterations" => self.mcts_iterations = parse_option_value(&arg, args.next())?,
+                "--time-ms" => {
+                    self.mcts_time_limit = Some(std::time::Duration::from_millis(
+                        parse_option_value(&arg, args.next())?,
+                    ));
+                }
+                "--seed" => self.seed = Some(parse_option_value(&arg, args.next())?),
+                "--threads" => self.threads = parse_option_value(&arg, args.next())?,
+                "--book" => self.book = Some(parse_option_value(&arg, args.next())?),
+                x => return Err(Error::UnknownOption(format!("Unknown option {x:?}"))),
+            }
+        }
+
+        // Order and Chaos and Qubic have their own classic board sizes
+        if !size_given {
+            match self.variant {
+                Variant::OrderAndChaos => self.board_size = OrderAndChaos::SIZE,
+                Variant::Qubic => self.board_size = Cube::SIZE,
+                _ => (),
+            }
+        }
+
+        if self.threads == 0 {
+            return Err(Error::OptionValue(
+                "--threads: at least one thread is needed".to_string(),
+            ));
+        }
+
+        if self.board_size == 0 {
+            return Err(Error::OptionValue(
+                "--size: the board needs at least one square".to_string(),
+            ));
+        }
+
+        if self.boards == 0 {
+            return Err(Error::OptionValue(
+                "--boards: notakto needs at least one board".to_string(),
+            ));
+        }
+
+        if self.games == 0 {
+            return Err(Error::OptionValue(
+                "--games: a match needs at least one game".to_string(),
+            ));
+        }
+
+        if self.depth == Some(0) {
+            return Err(Error::OptionValue(
+                "--depth: the engine needs to look at least one ply ahead".to_string(),
+            ));
+        }
+
+        if self.variant.has_own_engine()
+            && (self.engine != EngineType::AlphaBeta
+                || self.book.is_some()
+                || self.gravity
+                || self.torus
+                || !self.blocked.is_empty()
+                || !self.handicaps.is_empty()
+                || self.games != 1
+                || self.alternate_pieces)
+        {
+            return Err(Error::OptionValue(
+                "--variant: notakto, ultimate, qubic, three-pieces, morris, order-and-chaos, numerical and quantum have their own rules and computer opponents, so --engine, --book, --gravity, --torus, --blocked, --handicap, --games and --alternate-pieces can't be used"
+                    .to_string(),
+            ));
+        }
+
+        for (i, point) in self.blocked.iter().enumerate() {
+            if point.x >= self.board_size || point.y >= self.board_size {
+                return Err(Error::OptionValue(format!(
+                    "--blocked: {point} is not on a {0}x{0} board",
+                    self.board_size
+                )));
+            }
+            if self.blocked[..i].contains(point) {
+                return Err(Error::OptionValue(format!(
+                    "--blocked: {point} is blocked more than once"
+                )));
+            }
+        }
+
+        for (i, handicap) in self.handicaps.iter().enumerate() {
+            if self.handicaps[..i].contains(handicap) {
+                return Err(Error::OptionValue(format!(
+                    "--handicap: {handicap} is given more than once"
+                )));
+            }
+        }
+
+        // The swap is offered after the first move, which has to be a single move by one side
+        if self.handicaps.contains(&Handicap::Swap)
+            && (self.handicaps.contains(&Handicap::TwoMoves)
+                || self.handicaps.contains(&Handicap::Skip))
+        {
+            return Err(Error::OptionValue(
+                "--handicap swap: can't be combined with two-moves or skip".to_string(),
+            ));
+        }
+
+        if self.variant == Variant::Notakto && self.board_size != Notakto::SIZE {
+            return Err(Error::OptionValue(format!(
+                "--variant notakto: notakto is played on {0}x{0} boards",
+                Notakto::SIZE
+            )));
+        }
+
+        if self.variant == Variant::Ultimate && self.board_size != Ultimate::SIZE {
+            return Err(Error::OptionValue(format!(
+                "--variant ultimate: ultimate tic-tac-toe is played on {0}x{0} boards",
+                Ultimate::SIZE
+            )));
+        }
+
+        if matches!(self.variant, Variant::ThreePieces | Variant::Morris)
+            && self.board_size != Morris::SIZE
+        {
+            return Err(Error::OptionValue(format!(
+                "--variant {}: three pieces each are played on a {1}x{1} board",
+                if self.variant == Variant::Morris {
+                    "morris"
+                } else {
+                    "three-pieces"
+                },
+                Morris::SIZE
+            )));
+        }
+
+        if self.variant == Variant::OrderAndChaos && self.board_size < OrderAndChaos::LINE {
+            return Err(Error::OptionValue(format!(
+                "--variant order-and-chaos: five in a row needs a board at least {0}x{0}",
+                OrderAndChaos::LINE
+            )));
+        }
+
+        if self.variant == Variant::Quantum && self.board_size != Quantum::SIZE {
+            return Err(Error::OptionValue(format!(
+                "--variant quantum: quantum tic-tac-toe is played on a {0}x{0} board",
+                Quantum::SIZE
+            )));
+        }
+
+        // Only alpha-beta searches both pieces, and opening books only record squares
+        if self.variant == Variant::Wild
+            && (self.engine != EngineType::AlphaBeta || self.book.is_some())
+        {
+            return Err(Error::OptionValue(
+                "--variant wild: only the alpha-beta engine, without a book, can play wild tic-tac-toe"
+                    .to_string(),
+            ));
+        }
+
+        if self.engine == EngineType::Tablebase
+            && (self.board_size != Tablebase::SIZE
+                || self.rules() != Rules::default()
+                || !self.blocked.is_empty()
+                || self.handicaps.contains(&Handicap::TwoMoves)
+                || self.handicaps.contains(&Handicap::Skip))
+        {
+            return Err(Error::OptionValue(format!(
+                "--engine tablebase: the tablebase only covers standard {0}x{0} games, where the players take turns",
+                Tablebase::SIZE
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+// Parse the value of a "--name value" option, reporting which option was malformed on failure
+fn parse_option_value<T>(name: &str, value: Option<String>) -> Result<T, Error>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = value.ok_or_else(|| Error::OptionValue(format!("No value given for {name:?}")))?;
+    value
+        .parse()
+        .map_err(|e| Error::OptionValue(format!("{name}: could not parse {value:?} ({e})")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Parse a game's options after "x 1", returning why they were rejected if they were
+    fn rejection(options: &str) -> Option<String> {
+        let args = ["tic_tac_toe", "x", "1"]
+            .into_iter()
+            .chain(options.split_whitespace())
+            .map(String::from);
+        Config::build(args).err().map(|e| e.to_string())
+    }
+
+    #[test]
+    fn handicaps_combine_unless_they_clash() {
+        let config = Config::build(
+            "tic_tac_toe x 2 --handicap corner --handicap swap"
+                .split_whitespace()
+                .map(String::from),
+        )
+        .unwrap();
+        assert_eq!(config.handicaps, [Handicap::Corner, Handicap::Swap]);
+        assert_eq!(rejection("--handicap two-moves --handicap skip"), None);
+
+        assert!(rejection("--handicap corner --handicap corner")
+            .unwrap()
+            .contains("more than once"));
+        for clash in ["two-moves", "skip"] {
+            assert!(rejection(&format!("--handicap swap --handicap {clash}"))
+                .unwrap()
+                .starts_with("--handicap swap"));
+        }
+        assert!(rejection("--handicap queen")
+            .unwrap()
+            .starts_with("--handicap: could not parse"));
+        assert!(rejection("--handicap").is_some());
+    }
+
+    #[test]
+    fn tablebase_only_takes_handicaps_that_keep_turns_alternating() {
+        assert_eq!(rejection("--engine tablebase --handicap corner"), None);
+        assert_eq!(rejection("--engine tablebase --handicap swap"), None);
+        for handicap in ["two-moves", "skip"] {
+            assert!(
+                rejection(&format!("--engine tablebase --handicap {handicap}"))
+                    .unwrap()
+                    .starts_with("--engine tablebase")
+            );
+        }
+    }
+
+    #[test]
+    fn a_match_needs_at_least_one_game() {
+        assert_eq!(rejection("--games 3 --alternate-pieces"), None);
+        assert_eq!(rejection("--games 1"), None);
+        assert!(rejection("--games 0").unwrap().starts_with("--games"));
+        assert!(rejection("--games -1")
+            .unwrap()
+            .starts_with("--games: could not parse"));
+    }
+
+    #[test]
+    fn variants_with_their_own_engines_reject_the_board_options() {
+        assert_eq!(
+            rejection("--variant misere --games 3 --handicap swap"),
+            None
+        );
+        for variant in [
+            "notakto",
+            "ultimate",
+            "qubic",
+            "three-pieces",
+            "morris",
+            "order-and-chaos",
+            "numerical",
+            "quantum",
+        ] {
+            assert_eq!(rejection(&format!("--variant {variant}")), None);
+            for option in [
+                "--engine mcts",
+                "--book book.txt",
+                "--gravity",
+                "--torus",
+                "--blocked 0,0",
+                "--handicap corner",
+                "--games 2",
+                "--alternate-pieces",
+            ] {
+                assert!(
+                    rejection(&format!("--variant {variant} {option}"))
+                        .is_some_and(|e| e.starts_with("--variant")),
+                    "{variant} accepted {option}"
+                );
+            }
+        }
     }
 }
@@ -0,0 +1,115 @@
+use crate::board_info::{Player, Point, SquareType};
+use crate::config::{Config, EngineType};
+use crate::game_variant::{Negamax, Position, VariantEngine};
+use crate::mcts::Mcts;
+use crate::opening_book::{BookPlayer, OpeningBook};
+use crate::scoring::MoveScoreTurns;
+use crate::search::{PrincipalVariation, SearchContext, SearchStats};
+use crate::tablebase::TablebasePlayer;
+use crate::tic_tac_toe_board::Board;
+use std::time::{Duration, Instant};
+
+/// What a computer opponent decided, along with what it found out while deciding. This is printed
+/// after every computer move when running with `--verbose` (or `--engine-stats`).
+#[derive(Debug, Clone)]
+pub struct SearchReport {
+    pub player_move: Point,
+    /// The piece to place at `player_move`
+    pub piece: SquareType,
+    /// The exact result of the chosen move, for engines that search the game to its end
+    pub move_score_turns: Option<MoveScoreTurns>,
+    pub stats: SearchStats,
+    pub elapsed: Duration,
+    /// The expected line of best play, starting with `player_move`
+    pub principal_variation: PrincipalVariation,
+}
+
+impl std::fmt::Display for SearchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Engine stats: {}, {:?}", self.stats, self.elapsed)?;
+        if let Some(move_score_turns) = &self.move_score_turns {
+            writeln!(f, "Chosen move: {move_score_turns}")?;
+        }
+        write!(f, "Principal variation: {}", self.principal_variation)
+    }
+}
+
+/// The move-choosing interface shared by every computer opponent. Given the current `Board` and
+/// the `Player` the computer is playing as, choose the square the computer wants to play in.
+///
+/// The `player_move` of the returned report must be one of the board's blank squares, and its
+/// `piece` one of the board's `playable_pieces()`.
+pub trait ComputerPlayer {
+    fn choose_move(&mut self, board: &Board, player: &Player) -> SearchReport;
+}
+
+/// The computer opponent that plays a game on a single `Board`, chosen by `config.engine` and set
+/// up with the rest of `config`. Alpha-beta searches the `Position` to the end with `Negamax`.
+/// With `config.book`, the book's moves are played while the game is still in it.
+pub fn for_position(config: &Config) -> Box<dyn VariantEngine<Position>> {
+    let squares = (config.board_size * config.board_size) as u32;
+    let engine: Box<dyn VariantEngine<Position>> = match config.engine {
+        EngineType::AlphaBeta => Box::new(Negamax::new(squares)),
+        EngineType::Mcts => Box::new(Mcts::new(
+            config.mcts_iterations,
+            config.mcts_time_limit,
+            config.seed,
+        )),
+        EngineType::Tablebase => Box::new(TablebasePlayer::new()),
+    };
+    let Some(path) = &config.book else {
+        return engine;
+    };
+    match OpeningBook::load(path, config.board_size, config.rules()) {
+        Ok(book) => Box::new(BookPlayer::new(book, engine, config.seed)),
+        Err(e) => {
+            println!("Could not load the opening book {}: {e}", path.display());
+            engine
+        }
+    }
+}
+
+/// The computer opponent chosen by `config.engine` for searching a `Board` position on its own,
+/// as building an opening book does, set up with the rest of `config`
+pub fn from_config(config: &Config) -> Box<dyn ComputerPlayer> {
+    match config.engine {
+        EngineType::AlphaBeta => Box::new(AlphaBeta {
+            threads: config.threads,
+        }),
+        EngineType::Mcts => Box::new(Mcts::new(
+            config.mcts_iterations,
+            config.mcts_time_limit,
+            config.seed,
+        )),
+        EngineType::Tablebase => Box::new(TablebasePlayer::new()),
+    }
+}
+
+/// The original computer opponent: a full-depth minmax search with alpha-beta pruning. This
+/// plays perfectly but becomes impractically slow on boards larger than 3x3. Using more than one
+/// thread splits the root moves between threads (see `Board::alpha_beta_parallel()`).
+pub struct AlphaBeta {
+    pub threads: usize,
+}
+
+impl ComputerPlayer for AlphaBeta {
+    fn choose_move(&mut self, board: &Board, player: &Player) -> SearchReport {
+        let start = Instant::now();
+        let mut context = SearchContext::new(true);
+        let result = board.alpha_beta_parallel(
+            player,
+            board.blank_squares_remaining(),
+            self.threads,
+            &mut context,
+        );
+
+        SearchReport {
+            player_move: result.move_score_turns.player_move,
+            piece: result.move_score_turns.piece,
+            move_score_turns: Some(result.move_score_turns),
+            stats: context.stats,
+            elapsed: start.elapsed(),
+            principal_variation: result.principal_variation,
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use crate::board_info::{Player, Rules};
+use crate::scoring::GameStatus;
+use crate::symmetry::canonical_position;
+use crate::tic_tac_toe_board::Board;
+use std::collections::{HashMap, HashSet};
+
+/// Counts describing every game that can be played on an empty board, with X moving first. They
+/// are a quick way to check that game rules (and the engines playing them) behave as expected:
+/// on a 3x3 board there are famously 255,168 games and 5,478 distinct positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameTreeStats {
+    pub size: usize,
+    pub rules: Rules,
+    /// The number of distinct sequences of moves that play a game to its end
+    pub games: u128,
+    /// The number of distinct positions (including the empty board and finished games)
+    pub positions: usize,
+    /// The number of positions that are distinct up to rotations and reflections
+    pub symmetric_positions: usize,
+    /// The number of games X won, indexed by the number of moves the game took
+    pub x_wins: Vec<u128>,
+    /// The number of games O won, indexed by the number of moves the game took
+    pub o_wins: Vec<u128>,
+    /// The number of drawn games, indexed by the number of moves the game took
+    pub draws: Vec<u128>,
+    /// The fewest moves a game took
+    pub shortest_game: usize,
+    /// The most moves a game took
+    pub longest_game: usize,
+}
+
+impl GameTreeStats {
+    /// Walk the game tree of `empty_board` one ply at a time. Rather than playing out every game
+    /// separately, each position only keeps the number of move sequences that lead to it, so
+    /// positions reached by transposing moves are expanded once.
+    pub fn compute(empty_board: &Board) -> Self {
+        let size = empty_board.size();
+        let squares = size * size;
+        let mut stats = GameTreeStats {
+            size,
+            rules: empty_board.rules(),
+            games: 0,
+            positions: 0,
+            symmetric_positions: 0,
+            x_wins: vec![0; squares + 1],
+            o_wins: vec![0; squares + 1],
+            draws: vec![0; squares + 1],
+            shortest_game: squares,
+            longest_game: 0,
+        };
+
+        let mut layer = HashMap::from([(empty_board.position(), (empty_board.clone(), 1))]);
+        let mut player = Player::X;
+        for moves in 0..=squares {
+            stats.positions += layer.len();
+            stats.symmetric_positions += layer
+                .values()
+                .map(|(board, _)| canonical_position(board).0)
+                .collect::<HashSet<String>>()
+                .len();
+
+            let mut next_layer: HashMap<String, (Board, u128)> = HashMap::new();
+            for (board, paths) in layer.into_values() {
+                let results = match board.game_status() {
+                    GameStatus::StillPlaying => {
+                        for point in board.legal_moves() {
+                            for piece in board.playable_pieces(&player) {
+                                let mut new_board = board.clone();
+                                let _ = new_board.place(&point, piece, &player);
+                                next_layer
+                                    .entry(new_board.position())
+                                    .or_insert((new_board, 0))
+                                    .1 += paths;
+                            }
+                        }
+                        continue;
+                    }
+                    GameStatus::XWin => &mut stats.x_wins,
+                    GameStatus::OWin => &mut stats.o_wins,
+                    GameStatus::Draw => &mut stats.draws,
+                };
+                results[moves] += paths;
+                stats.games += paths;
+                stats.shortest_game = stats.shortest_game.min(moves);
+                stats.longest_game = stats.longest_game.max(moves);
+            }
+            layer = next_layer;
+            player = player.other();
+        }
+        stats
+    }
+}
+
+/// A summary followed by a table of results by the number of moves played
+impl std::fmt::Display for GameTreeStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let variant: String = [
+            (self.rules.misere, "misere "),
+            (self.rules.wild, "wild "),
+            (self.rules.gravity, "gravity "),
+            (self.rules.torus, "torus "),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, name)| *name)
+        .collect();
+        writeln!(
+            f,
+            "Game tree of the {0}x{0} {variant}board (X moves first)",
+            self.size
+        )?;
+        writeln!(f, "Games: {}", self.games)?;
+        writeln!(
+            f,
+            "Distinct positions: {} ({} up to symmetry)",
+            self.positions, self.symmetric_positions
+        )?;
+        writeln!(f, "Shortest game: {} moves", self.shortest_game)?;
+        writeln!(f, "Longest game: {} moves", self.longest_game)?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:>6} {:>14} {:>14} {:>14}",
+            "Moves", "X wins", "O wins", "Draws"
+        )?;
+        for moves in 0..self.x_wins.len() {
+            let results = [self.x_wins[moves], self.o_wins[moves], self.draws[moves]];
+            if results != [0, 0, 0] {
+                let [x_wins, o_wins, draws] = results;
+                writeln!(f, "{moves:>6} {x_wins:>14} {o_wins:>14} {draws:>14}")?;
+            }
+        }
+        write!(
+            f,
+            "{:>6} {:>14} {:>14} {:>14}",
+            "Total",
+            self.x_wins.iter().sum::<u128>(),
+            self.o_wins.iter().sum::<u128>(),
+            self.draws.iter().sum::<u128>()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classic_board_statistics() {
+        let stats = GameTreeStats::compute(&Board::initialize_blank_board(3));
+        assert_eq!(stats.games, 255_168);
+        assert_eq!(stats.positions, 5_478);
+        assert_eq!(stats.symmetric_positions, 765);
+        assert_eq!(stats.shortest_game, 5);
+        assert_eq!(stats.longest_game, 9);
+        assert_eq!(stats.x_wins, [0, 0, 0, 0, 0, 1_440, 0, 47_952, 0, 81_792]);
+        assert_eq!(stats.o_wins, [0, 0, 0, 0, 0, 0, 5_328, 0, 72_576, 0]);
+        assert_eq!(stats.draws, [0, 0, 0, 0, 0, 0, 0, 0, 0, 46_080]);
+    }
+
+    #[test]
+    fn tiny_boards() {
+        // X wins with the first move on a 1x1 board
+        let stats = GameTreeStats::compute(&Board::initialize_blank_board(1));
+        assert_eq!((stats.games, stats.positions), (1, 2));
+        assert_eq!(stats.x_wins, [0, 1]);
+
+        // Every 2x2 game is won by X on the third move
+        let stats = GameTreeStats::compute(&Board::initialize_blank_board(2));
+        assert_eq!(stats.games, 4 * 3 * 2);
+        assert_eq!((stats.shortest_game, stats.longest_game), (3, 3));
+        assert_eq!(stats.x_wins.iter().sum::<u128>(), stats.games);
+    }
+
+    #[test]
+    fn misere_swaps_the_winners() {
+        let standard = GameTreeStats::compute(&Board::initialize_blank_board(3));
+        let misere = GameTreeStats::compute(&Board::with_rules(
+            3,
+            Rules {
+                misere: true,
+                ..Default::default()
+            },
+        ));
+        assert_eq!(misere.games, standard.games);
+        assert_eq!(misere.x_wins, standard.o_wins);
+        assert_eq!(misere.o_wins, standard.x_wins);
+    }
+
+    #[test]
+    fn torus_games_are_never_drawn() {
+        let torus = GameTreeStats::compute(&Board::with_rules(
+            3,
+            Rules {
+                torus: true,
+                ..Default::default()
+            },
+        ));
+        assert_eq!(torus.draws.iter().sum::<u128>(), 0);
+        // Shifting the board is a symmetry too, so far fewer positions are distinct
+        assert_eq!(torus.symmetric_positions, 102);
+    }
+}
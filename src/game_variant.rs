@@ -0,0 +1,992 @@
+use crate::board_info::{Opponent, Player, Point, SquareType};
+use crate::config::{Config, Handicap, MoveFirst, PlayerIsX};
+use crate::scoring::{GameStatus, Scoreboard};
+use crate::tic_tac_toe_board::Board;
+use std::io;
+use std::time::{Duration, Instant};
+
+/// The score of a won game for the side to move, and minus the score of a lost one
+pub const WIN: i32 = 1_000_000;
+
+/// The rules of a two-player game: which moves are legal, what they do, when the game is over and
+/// how it went. `Negamax`, `Mcts` and the command line loop (`run()`) only know a game through
+/// this trait, so a new variant only has to implement it, and a computer opponent if neither
+/// engine will do.
+pub trait GameVariant: Clone + std::fmt::Display {
+    type Move: Clone + PartialEq + std::fmt::Debug + std::fmt::Display;
+
+    /// Who moves next. Some games give the same player several moves in a row.
+    fn to_move(&self) -> Player;
+
+    /// Every move that can be played next, in a stable order. Empty once the game is over.
+    fn legal_moves(&self) -> Vec<Self::Move>;
+
+    /// The moves `Negamax` searches, most promising first. Games that can tell which moves are
+    /// forced (e.g. taking a win or blocking one) can leave out the rest. Every legal move, in
+    /// order, unless the game knows better.
+    fn search_moves(&self) -> Vec<Self::Move> {
+        self.legal_moves()
+    }
+
+    /// Play `player_move` for the player to move
+    fn play(&mut self, player_move: &Self::Move) -> Result<(), String>;
+
+    fn is_over(&self) -> bool;
+
+    /// The value of the game for the player to move. A finished game scores `WIN` if they won,
+    /// `-WIN` if they lost and 0 for a draw, or anything in between for results worth part of a
+    /// win. An unfinished game scores an estimate well inside those bounds, 0 if there isn't one.
+    fn score(&self) -> i32;
+
+    /// The move a player typed in
+    fn parse_move(&self, input: &str) -> Result<Self::Move, String>;
+
+    /// Anything the player to move needs to be told before choosing their move
+    fn note(&self) -> Option<String> {
+        None
+    }
+
+    /// How the game stands, for the final message (e.g. "XWin")
+    fn status(&self) -> String;
+
+    /// Who won the game, once it is over: the player to move if it scores above 0 for them, the
+    /// other player if it scores below, and no one if it is a draw
+    fn winner(&self) -> Option<Player> {
+        match self.score().cmp(&0) {
+            std::cmp::Ordering::Greater => Some(self.to_move()),
+            std::cmp::Ordering::Less => Some(self.to_move().other()),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// Whether the player to move may swap sides, taking the move just played as their own
+    fn offers_swap(&self) -> bool {
+        false
+    }
+
+    /// The player to move takes the move just played as their own, and the other player moves
+    /// next in their place
+    fn swap_sides(&mut self) {}
+}
+
+/// What a `VariantEngine` chose and how it got there
+#[derive(Debug, Clone)]
+pub struct Report<M> {
+    pub player_move: M,
+    /// The evaluation of the move for the player who chose it, if the engine has one
+    pub score: Option<i32>,
+    /// The line of play the engine expects, starting with `player_move`
+    pub line: Vec<M>,
+    /// The number of positions searched
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+impl<M> std::fmt::Display for Report<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Searched {} positions in {:.1?}",
+            self.nodes, self.elapsed
+        )?;
+        if let Some(score) = self.score {
+            write!(f, " (score {score})")?;
+        }
+        Ok(())
+    }
+}
+
+impl<M: std::fmt::Display> Report<M> {
+    /// The expected line of play, e.g. "0 0 X → 1 1 O → 2 2 X", followed by the score if there
+    /// is one
+    pub fn hint(&self) -> String {
+        let line = if self.line.is_empty() {
+            self.player_move.to_string()
+        } else {
+            self.line
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join(" → ")
+        };
+        match self.score {
+            Some(score) => format!("{line} (score {score})"),
+            None => line,
+        }
+    }
+}
+
+/// A computer opponent for any `GameVariant`
+pub trait VariantEngine<G: GameVariant> {
+    /// The move to play for the player to move in `game`, which must not be over
+    fn choose_move(&mut self, game: &G) -> Report<G::Move>;
+}
+
+/// A depth-limited alpha-beta search through `GameVariant::search_moves()`. Positions `depth` moves
+/// ahead are scored by `GameVariant::score()`, and finished games found sooner score slightly
+/// further from 0. The line it reports is the best play it found for both sides, as far as it
+/// looked.
+#[derive(Debug, Clone)]
+pub struct Negamax {
+    pub depth: u32,
+    nodes: u64,
+}
+
+impl Negamax {
+    pub fn new(depth: u32) -> Self {
+        Self { depth, nodes: 0 }
+    }
+
+    // The value of `game` for `player`, whether or not they are to move, and the line that leads
+    // to it
+    fn score_for<G: GameVariant>(
+        &mut self,
+        game: &G,
+        player: &Player,
+        depth: u32,
+        alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> (i32, Vec<G::Move>) {
+        if game.to_move() == *player {
+            self.negamax(game, depth, alpha, beta, ply)
+        } else {
+            let (score, line) = self.negamax(game, depth, -beta, -alpha, ply);
+            (-score, line)
+        }
+    }
+
+    // The value of `game` for the player to move, `ply` moves below the root, and the best line
+    // from it
+    fn negamax<G: GameVariant>(
+        &mut self,
+        game: &G,
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> (i32, Vec<G::Move>) {
+        self.nodes += 1;
+        if game.is_over() {
+            let score = game.score();
+            return (score - score.signum() * ply, Vec::new());
+        }
+        if depth == 0 {
+            return (game.score(), Vec::new());
+        }
+
+        let player = game.to_move();
+        let mut best = (-WIN - 1, Vec::new());
+        for player_move in game.search_moves() {
+            let mut new_game = game.clone();
+            let _ = new_game.play(&player_move);
+            let (score, line) = self.score_for(&new_game, &player, depth - 1, alpha, beta, ply + 1);
+            if score > best.0 {
+                best = (score, std::iter::once(player_move).chain(line).collect());
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+impl<G: GameVariant> VariantEngine<G> for Negamax {
+    fn choose_move(&mut self, game: &G) -> Report<G::Move> {
+        let start = Instant::now();
+        self.nodes = 0;
+        let player = game.to_move();
+        let mut best: Option<(G::Move, i32, Vec<G::Move>)> = None;
+        for player_move in game.search_moves() {
+            let mut new_game = game.clone();
+            let _ = new_game.play(&player_move);
+            let alpha = best.as_ref().map_or(-WIN - 1, |(_, best, _)| *best);
+            let (score, line) = self.score_for(
+                &new_game,
+                &player,
+                self.depth.saturating_sub(1),
+                alpha,
+                WIN + 1,
+                1,
+            );
+            if best.as_ref().is_none_or(|(_, best, _)| score > *best) {
+                best = Some((player_move, score, line));
+            }
+        }
+        let (player_move, score, line) =
+            best.expect("a game that is still being played has a move");
+        Report {
+            line: std::iter::once(player_move.clone()).chain(line).collect(),
+            player_move,
+            score: Some(score),
+            nodes: self.nodes,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+#[rustfmt::skip]
+const OUTRO_MESSAGE: &str =
+    "\n##################################################\n\
+     \
+     \nThanks for playing!\
+     \n-------------------\n";
+
+/// Play against `engine` on the command line: print `intro`, then play matches of `config.games`
+/// games, each started by `new_game` for whoever moves first and the piece the user plays, and say
+/// how every game ended. The user's piece and whether they move first come from `config`. Over a
+/// match the first move alternates, and with `--alternate-pieces` so does the user's piece. A
+/// match ends early once one side can't be caught. After each game of a match the user is asked
+/// whether to play the next one, and after the match whether to play another.
+pub fn run<G: GameVariant>(
+    config: &Config,
+    intro: &str,
+    mut new_game: impl FnMut(Player, Player) -> G,
+    engine: &mut dyn VariantEngine<G>,
+) {
+    let PlayerIsX(user_is_x) = config.player_piece_type;
+    let MoveFirst(user_first) = config.first_or_second;
+
+    println!("{intro}");
+    loop {
+        let mut scoreboard = Scoreboard::default();
+        let mut stopped = false;
+        for round in 0..config.games {
+            let alternate = round % 2 == 1;
+            let user = if user_is_x != (alternate && config.alternate_pieces) {
+                Player::X
+            } else {
+                Player::O
+            };
+            // Whoever starts plays their own piece, so O moves first when the starting player has O
+            let first = if user_first != alternate {
+                user
+            } else {
+                user.other()
+            };
+
+            if config.games > 1 {
+                println!("\nGame {} of {}", round + 1, config.games);
+            }
+            let (game, user) = play(new_game(first, user), engine, user, config.verbose);
+            // The input ran out before the game was over
+            if !game.is_over() {
+                stopped = true;
+                break;
+            }
+            println!("Final Status: {}", game.status());
+            let game_status = match game.winner() {
+                Some(Player::X) => GameStatus::XWin,
+                Some(Player::O) => GameStatus::OWin,
+                None => GameStatus::Draw,
+            };
+            scoreboard.record(game_status, &user);
+            if config.games > 1 {
+                println!("Score: {scoreboard}");
+            }
+
+            if scoreboard.is_decided(config.games - round - 1) {
+                break;
+            }
+            if !ask_yes_no("Play the next game? (y/n)") {
+                stopped = true;
+                break;
+            }
+        }
+        if config.games > 1 && scoreboard.games() > 0 {
+            println!("\n{}", scoreboard.summary());
+        }
+        // Stopping partway through a match stops playing altogether, rather than asking again
+        if stopped || !ask_yes_no("Play again? (y/n)") {
+            break;
+        }
+    }
+    println!("{OUTRO_MESSAGE}");
+}
+
+/// Play `game` on the command line, with the user as `user` and `engine` as their opponent.
+/// Typing "hint" shows the engine's suggestion. Returns the game, which is left unfinished if the
+/// input runs out, and the side the user finished on, which a swap may have changed.
+pub fn play<G: GameVariant>(
+    mut game: G,
+    engine: &mut dyn VariantEngine<G>,
+    mut user: Player,
+    verbose: bool,
+) -> (G, Player) {
+    // Where the game started, to see whether the computer would have opened the same way
+    let opening = game.clone();
+    println!("Initial board:");
+    'game: while !game.is_over() {
+        println!("\n{game}\n");
+        if let Some(note) = game.note() {
+            println!("{note}");
+        }
+        let current_player = if game.to_move() == user {
+            Opponent::User
+        } else {
+            Opponent::Computer
+        };
+        let played = match current_player {
+            Opponent::User => {
+                println!("Your move: ");
+                loop {
+                    let mut input = String::new();
+                    match io::stdin().read_line(&mut input) {
+                        // Running out of input leaves the game unfinished
+                        Ok(0) => break 'game,
+                        Ok(_) => (),
+                        Err(e) => {
+                            println!("{e}");
+                            continue;
+                        }
+                    }
+                    if input.trim().eq_ignore_ascii_case("hint") {
+                        println!("Hint: {}", engine.choose_move(&game).hint());
+                        continue;
+                    }
+                    match game.parse_move(&input).and_then(|player_move| {
+                        game.play(&player_move)?;
+                        Ok(player_move)
+                    }) {
+                        Ok(player_move) => break player_move,
+                        Err(e) => println!("{e}"),
+                    }
+                }
+            }
+            Opponent::Computer => {
+                let report = engine.choose_move(&game);
+                println!("Opponent's move:\n{}", report.player_move);
+                if verbose {
+                    println!("{report}");
+                }
+                game.play(&report.player_move)
+                    .expect("the engine should only choose legal moves");
+                report.player_move
+            }
+        };
+
+        if game.offers_swap() && !game.is_over() {
+            let swap = match current_player {
+                // The computer swaps for the move it would have opened with itself
+                Opponent::User => engine.choose_move(&opening).player_move == played,
+                Opponent::Computer => ask_yes_no(&format!(
+                    "Swap sides, taking the opponent's first move as yours and playing {}? (y/n)",
+                    user.other()
+                )),
+            };
+            if swap {
+                if current_player == Opponent::User {
+                    println!("Opponent swaps sides and takes your first move as its own");
+                }
+                game.swap_sides();
+                user = user.other();
+                println!("You are now playing {user}");
+            }
+        }
+    }
+    println!("\nFinal Board: \n{game}\n");
+    (game, user)
+}
+
+// Ask the user a yes or no question. Running out of input counts as no.
+fn ask_yes_no(question: &str) -> bool {
+    println!("{question}");
+    loop {
+        let mut answer = String::new();
+        match io::stdin().read_line(&mut answer) {
+            Ok(0) => return false,
+            Ok(_) => (),
+            Err(e) => {
+                println!("{e}");
+                continue;
+            }
+        }
+        match answer.trim() {
+            "y" | "Y" | "yes" => return true,
+            "n" | "N" | "no" => return false,
+            _ => println!("Expected \"y\" or \"n\""),
+        }
+    }
+}
+
+/// A piece placed on a square of a `Board`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub point: Point,
+    pub piece: SquareType,
+}
+
+/// Written the way moves are entered, e.g. "1 1 X"
+impl std::fmt::Display for Placement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.point.x, self.point.y, self.piece)
+    }
+}
+
+// Where a game is in its order of turns: who is to move, the piece the user plays, and how many
+// turns each side has had. The handicaps decide how one turn leads to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Turn {
+    mover: Opponent,
+    user_piece: Player,
+    user_turns: usize,
+    computer_turns: usize,
+}
+
+impl Turn {
+    fn first(mover: Opponent, user_piece: Player) -> Self {
+        Self {
+            mover,
+            user_piece,
+            user_turns: 0,
+            computer_turns: 0,
+        }
+    }
+
+    // The piece the player to move places
+    fn piece(&self) -> Player {
+        match self.mover {
+            Opponent::User => self.user_piece,
+            Opponent::Computer => self.user_piece.other(),
+        }
+    }
+
+    // With the skip handicap the computer passes on its first turn
+    fn is_pass(&self, handicaps: &[Handicap]) -> bool {
+        self.mover == Opponent::Computer
+            && self.computer_turns == 0
+            && handicaps.contains(&Handicap::Skip)
+    }
+
+    // Whether this is the computer's first move, not counting a passed turn
+    fn is_computer_first_move(&self, handicaps: &[Handicap]) -> bool {
+        self.mover == Opponent::Computer
+            && self.computer_turns == usize::from(handicaps.contains(&Handicap::Skip))
+    }
+
+    // Whether this is the user's second move of their first turn
+    fn is_second_move(&self, handicaps: &[Handicap]) -> bool {
+        self.mover == Opponent::User
+            && self.user_turns == 1
+            && handicaps.contains(&Handicap::TwoMoves)
+    }
+
+    // The turn after this one has been played. The user's first turn is two moves long with the
+    // two-moves handicap.
+    fn next(self, handicaps: &[Handicap]) -> Self {
+        let mut next = self;
+        match self.mover {
+            Opponent::User => next.user_turns += 1,
+            Opponent::Computer => next.computer_turns += 1,
+        }
+        if !next.is_second_move(handicaps) {
+            next.mover = self.mover.other();
+        }
+        next
+    }
+
+    // With the swap rule, the player to move after the first move may take it as their own
+    fn offers_swap(&self, handicaps: &[Handicap]) -> bool {
+        handicaps.contains(&Handicap::Swap) && self.user_turns + self.computer_turns == 1
+    }
+
+    // The player to move has swapped sides: the first move is theirs, and the other player moves
+    // next with the other piece
+    fn swapped(self) -> Self {
+        Self {
+            mover: self.mover.other(),
+            user_piece: self.user_piece.other(),
+            ..self
+        }
+    }
+}
+
+/// Tic-tac-toe on a `Board` as a `GameVariant`. The board's `Rules` decide the rest, so misère,
+/// wild, gravity and torus games all come with it, and the `Handicap`s a game starts with decide
+/// how its turns go.
+#[derive(Debug, Clone)]
+pub struct Position {
+    board: Board,
+    turn: Turn,
+    handicaps: Vec<Handicap>,
+    /// Whether the computer has just passed its first turn
+    passed: bool,
+}
+
+impl Position {
+    /// `board` with `to_move` to move, and the players taking turns from there
+    pub fn new(board: Board, to_move: Player) -> Self {
+        Self::with_handicaps(board, to_move, to_move, &[])
+    }
+
+    /// `board` with `first` to move and the user playing `user`, in a game started with
+    /// `handicaps`
+    pub fn with_handicaps(
+        board: Board,
+        first: Player,
+        user: Player,
+        handicaps: &[Handicap],
+    ) -> Self {
+        let mover = if first == user {
+            Opponent::User
+        } else {
+            Opponent::Computer
+        };
+        let mut position = Self {
+            board,
+            turn: Turn::first(mover, user),
+            handicaps: handicaps.to_vec(),
+            passed: false,
+        };
+        position.pass();
+        position
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    // With the skip handicap the computer's first turn is passed as soon as it comes up
+    fn pass(&mut self) {
+        self.passed = self.turn.is_pass(&self.handicaps);
+        if self.passed {
+            self.turn = self.turn.next(&self.handicaps);
+        }
+    }
+
+    // With the corner handicap the computer's first move has to be in a corner, if one is free
+    fn corner_move_due(&self) -> bool {
+        self.handicaps.contains(&Handicap::Corner)
+            && self.turn.is_computer_first_move(&self.handicaps)
+    }
+
+    // The squares the player to move can play in
+    fn legal_points(&self) -> Vec<Point> {
+        let points = self.board.legal_moves();
+        if !self.corner_move_due() {
+            return points;
+        }
+        let last = self.board.size() - 1;
+        let corners: Vec<Point> = points
+            .iter()
+            .copied()
+            .filter(|point| [0, last].contains(&point.x) && [0, last].contains(&point.y))
+            .collect();
+        if corners.is_empty() {
+            points
+        } else {
+            corners
+        }
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.board)
+    }
+}
+
+impl GameVariant for Position {
+    type Move = Placement;
+
+    fn to_move(&self) -> Player {
+        self.turn.piece()
+    }
+
+    fn legal_moves(&self) -> Vec<Placement> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        let pieces = self.board.playable_pieces(&self.to_move());
+        self.legal_points()
+            .into_iter()
+            .flat_map(|point| pieces.iter().map(move |&piece| Placement { point, piece }))
+            .collect()
+    }
+
+    fn play(&mut self, player_move: &Placement) -> Result<(), String> {
+        if self.is_over() {
+            return Err("The game is over".to_string());
+        }
+        let to_move = self.to_move();
+        if !self
+            .board
+            .playable_pieces(&to_move)
+            .contains(&player_move.piece)
+        {
+            return Err(format!("{to_move} can't place {}", player_move.piece));
+        }
+        if self.corner_move_due() && !self.legal_points().contains(&player_move.point) {
+            return Err(format!(
+                "The opponent's first move has to be in a corner, and {} isn't one",
+                player_move.point
+            ));
+        }
+        self.board
+            .place(&player_move.point, player_move.piece, &to_move)?;
+        self.turn = self.turn.next(&self.handicaps);
+        self.pass();
+        Ok(())
+    }
+
+    fn is_over(&self) -> bool {
+        self.board.game_status() != GameStatus::StillPlaying
+    }
+
+    // Under misère and wild rules, also who completed the line
+    fn status(&self) -> String {
+        let status = self.board.game_status();
+        let rules = self.board.rules();
+        match self.winner() {
+            Some(winner) if self.is_over() && rules.misere => format!(
+                "{status:?}\n{} completed a line, so {winner} wins",
+                winner.other()
+            ),
+            Some(winner) if self.is_over() && rules.wild => {
+                format!("{status:?}\n{winner} completed a line, so {winner} wins")
+            }
+            _ => format!("{status:?}"),
+        }
+    }
+
+    fn score(&self) -> i32 {
+        match (self.board.game_status(), self.to_move()) {
+            (GameStatus::XWin, Player::X) | (GameStatus::OWin, Player::O) => WIN,
+            (GameStatus::XWin, Player::O) | (GameStatus::OWin, Player::X) => -WIN,
+            (GameStatus::Draw | GameStatus::StillPlaying, _) => 0,
+        }
+    }
+
+    // "x y", or "x y piece" to choose the piece under wild rules. Under gravity rules just the
+    // column will do.
+    fn parse_move(&self, input: &str) -> Result<Placement, String> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let number = |word: &str| word.parse::<usize>().map_err(|e| e.to_string());
+        let point = match words[..] {
+            [column] if self.board.rules().gravity => self
+                .board
+                .drop_point(number(column)?)
+                .ok_or_else(|| format!("Column {column} is full"))?,
+            [x, y] | [x, y, _] => Point {
+                x: number(x)?,
+                y: number(y)?,
+            },
+            _ => {
+                return Err(format!(
+                    "Incorrect number of arguments. Expected 2 (x y) or 3 (x y piece). Got {}",
+                    words.len()
+                ))
+            }
+        };
+        let piece = match words[..] {
+            [_, _, piece] => match piece.to_ascii_uppercase().as_str() {
+                "X" => SquareType::X,
+                "O" => SquareType::O,
+                _ => return Err(format!("Expected the piece X or O. Got {piece}")),
+            },
+            _ => self.to_move().square_type(),
+        };
+        Ok(Placement { point, piece })
+    }
+
+    fn note(&self) -> Option<String> {
+        if self.passed {
+            Some("Opponent passes its first turn".to_string())
+        } else if self.turn.is_second_move(&self.handicaps) {
+            Some("Your first turn is two moves long. Play your second move".to_string())
+        } else {
+            None
+        }
+    }
+
+    fn offers_swap(&self) -> bool {
+        self.turn.offers_swap(&self.handicaps)
+    }
+
+    fn swap_sides(&mut self) {
+        self.turn = self.turn.swapped();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_info::Rules;
+    use crate::mcts::Mcts;
+
+    fn position(squares: &str, rules: Rules, to_move: Player) -> Position {
+        Position::new(Board::from_position(squares, rules).unwrap(), to_move)
+    }
+
+    #[test]
+    fn negamax_solves_standard_tic_tac_toe() {
+        let empty = Position::new(Board::initialize_blank_board(3), Player::X);
+        let report = Negamax::new(9).choose_move(&empty);
+        assert_eq!(report.score, Some(0));
+
+        // X to move takes the top row rather than blocking O
+        let game = position("XXB/OOB/BBB", Rules::default(), Player::X);
+        let report = Negamax::new(9).choose_move(&game);
+        assert_eq!(
+            report.player_move,
+            Placement {
+                point: Point { x: 0, y: 2 },
+                piece: SquareType::X
+            }
+        );
+        assert!(report.score.unwrap() > WIN / 2);
+    }
+
+    #[test]
+    fn negamax_reports_the_line_it_expects() {
+        let mut game = Position::new(Board::initialize_blank_board(3), Player::X);
+        let report = Negamax::new(9).choose_move(&game);
+        assert_eq!(report.line[0], report.player_move);
+
+        // Best play from an empty board fills it without either side winning
+        for player_move in &report.line {
+            game.play(player_move).unwrap();
+        }
+        assert_eq!(report.line.len(), 9);
+        assert_eq!(game.board().game_status(), GameStatus::Draw);
+        assert!(report.hint().ends_with("(score 0)"));
+    }
+
+    #[test]
+    fn board_rules_carry_over_to_the_variant() {
+        // Under misère rules X avoids finishing the top row
+        let misere = Rules {
+            misere: true,
+            ..Rules::default()
+        };
+        let game = position("XXB/OOB/OBB", misere, Player::X);
+        let report = Negamax::new(9).choose_move(&game);
+        assert_ne!(report.player_move.point, Point { x: 0, y: 2 });
+
+        // Under gravity rules only the lowest blank square of each column can be played
+        let gravity = Rules {
+            gravity: true,
+            ..Rules::default()
+        };
+        let game = position("BBB/BBB/BBB", gravity, Player::X);
+        let points: Vec<Point> = game.legal_moves().iter().map(|m| m.point).collect();
+        assert_eq!(points, game.board().legal_moves());
+        assert!(points.iter().all(|point| point.x == 2));
+        assert_eq!(game.parse_move("1").unwrap().point, Point { x: 2, y: 1 });
+
+        // Under wild rules either piece can be played
+        let wild = Rules {
+            wild: true,
+            ..Rules::default()
+        };
+        let game = position("BBB/BBB/BBB", wild, Player::X);
+        assert_eq!(game.legal_moves().len(), 18);
+    }
+
+    #[test]
+    fn mcts_takes_a_winning_move() {
+        let game = position("OOB/XXB/BBB", Rules::default(), Player::X);
+        let report = Mcts::new(2000, None, Some(7)).choose_move(&game);
+        assert_eq!(report.player_move.point, Point { x: 1, y: 2 });
+        assert_eq!(report.line[0], report.player_move);
+    }
+
+    #[test]
+    fn corner_handicap_keeps_the_computers_first_move_in_a_corner() {
+        let corner = [Handicap::Corner];
+        let board = Board::initialize_blank_board(3);
+        let mut game = Position::with_handicaps(board, Player::X, Player::X, &corner);
+        assert_eq!(game.legal_moves().len(), 9);
+        game.play(&game.parse_move("0 0").unwrap()).unwrap();
+
+        let corners = [(0, 2), (2, 0), (2, 2)].map(|(x, y)| Point { x, y });
+        let points: Vec<Point> = game.legal_moves().iter().map(|m| m.point).collect();
+        assert_eq!(points, corners);
+        assert!(game.play(&game.parse_move("1 1").unwrap()).is_err());
+        let report = Negamax::new(9).choose_move(&game);
+        assert!(corners.contains(&report.player_move.point));
+
+        // Only the computer's first move is held to the corners
+        game.play(&report.player_move).unwrap();
+        game.play(&game.parse_move("1 1").unwrap()).unwrap();
+        assert_eq!(game.legal_moves().len(), 6);
+
+        // Under gravity only the bottom corners can be played
+        let gravity = Rules {
+            gravity: true,
+            ..Rules::default()
+        };
+        let board = Board::with_rules(3, gravity);
+        let game = Position::with_handicaps(board, Player::X, Player::O, &corner);
+        let points: Vec<Point> = game.legal_moves().iter().map(|m| m.point).collect();
+        assert_eq!(points, [Point { x: 2, y: 0 }, Point { x: 2, y: 2 }]);
+    }
+
+    #[test]
+    fn skip_and_two_moves_handicaps_change_who_moves_next() {
+        let board = Board::initialize_blank_board(3);
+        let handicaps = [Handicap::Skip, Handicap::TwoMoves];
+        let mut game = Position::with_handicaps(board, Player::X, Player::O, &handicaps);
+        // The computer's pass leaves the user to move first, twice
+        assert_eq!(game.to_move(), Player::O);
+        assert_eq!(game.note().unwrap(), "Opponent passes its first turn");
+        game.play(&game.parse_move("1 1").unwrap()).unwrap();
+        assert_eq!(game.to_move(), Player::O);
+        assert!(game.note().unwrap().ends_with("Play your second move"));
+        game.play(&game.parse_move("0 0").unwrap()).unwrap();
+        assert_eq!(game.to_move(), Player::X);
+        assert_eq!(game.note(), None);
+
+        // With two moves in a row, the user can't be stopped from winning
+        let report = Negamax::new(9).choose_move(&game);
+        assert!(report.score.unwrap() < -WIN / 2);
+    }
+
+    #[test]
+    fn swap_handicap_hands_the_first_move_over_once() {
+        let board = Board::initialize_blank_board(3);
+        let mut game = Position::with_handicaps(board, Player::X, Player::X, &[Handicap::Swap]);
+        assert!(!game.offers_swap());
+        game.play(&game.parse_move("1 1").unwrap()).unwrap();
+        assert!(game.offers_swap());
+
+        // The piece to move stays O, which is now the user's
+        game.swap_sides();
+        assert_eq!(game.to_move(), Player::O);
+        game.play(&game.parse_move("0 0").unwrap()).unwrap();
+        assert!(!game.offers_swap());
+        assert_eq!(game.to_move(), Player::X);
+    }
+
+    #[test]
+    fn misere_status_says_who_completed_the_line() {
+        let misere = Rules {
+            misere: true,
+            ..Rules::default()
+        };
+        let game = position("XXX/OOB/BBB", misere, Player::O);
+        assert_eq!(game.winner(), Some(Player::O));
+        assert_eq!(game.status(), "OWin\nX completed a line, so O wins");
+        let game = position("XXX/OOB/BBB", Rules::default(), Player::O);
+        assert_eq!(game.status(), "XWin");
+    }
+
+    // The mover and piece of each of the first `turns` turns, with no piece for a passed turn.
+    // `swap` is whether the player offered a swap takes it.
+    fn turn_order(
+        handicaps: &[Handicap],
+        first: Opponent,
+        user_piece: Player,
+        swap: bool,
+        turns: usize,
+    ) -> Vec<(Opponent, Option<Player>)> {
+        let mut turn = Turn::first(first, user_piece);
+        let mut order = Vec::new();
+        for _ in 0..turns {
+            let piece = (!turn.is_pass(handicaps)).then(|| turn.piece());
+            order.push((turn.mover, piece));
+            turn = turn.next(handicaps);
+            if swap && turn.offers_swap(handicaps) {
+                turn = turn.swapped();
+            }
+        }
+        order
+    }
+
+    #[test]
+    fn two_moves_handicap_gives_the_user_two_moves_on_their_first_turn() {
+        use Opponent::{Computer, User};
+        let handicaps = [Handicap::TwoMoves];
+        assert_eq!(
+            turn_order(&handicaps, User, Player::X, false, 5),
+            vec![
+                (User, Some(Player::X)),
+                (User, Some(Player::X)),
+                (Computer, Some(Player::O)),
+                (User, Some(Player::X)),
+                (Computer, Some(Player::O)),
+            ]
+        );
+        assert_eq!(
+            turn_order(&handicaps, Computer, Player::O, false, 5),
+            vec![
+                (Computer, Some(Player::X)),
+                (User, Some(Player::O)),
+                (User, Some(Player::O)),
+                (Computer, Some(Player::X)),
+                (User, Some(Player::O)),
+            ]
+        );
+    }
+
+    #[test]
+    fn skip_handicap_passes_the_computers_first_turn() {
+        use Opponent::{Computer, User};
+        let handicaps = [Handicap::Skip, Handicap::Corner];
+        assert_eq!(
+            turn_order(&handicaps, Computer, Player::O, false, 4),
+            vec![
+                (Computer, None),
+                (User, Some(Player::O)),
+                (Computer, Some(Player::X)),
+                (User, Some(Player::O)),
+            ]
+        );
+        assert_eq!(
+            turn_order(&handicaps, User, Player::X, false, 4),
+            vec![
+                (User, Some(Player::X)),
+                (Computer, None),
+                (User, Some(Player::X)),
+                (Computer, Some(Player::O)),
+            ]
+        );
+
+        // The corner handicap applies to the computer's first real move, after the pass
+        let pass = Turn::first(Computer, Player::O);
+        assert!(!pass.is_computer_first_move(&handicaps));
+        let first_move = pass.next(&handicaps).next(&handicaps);
+        assert!(first_move.is_computer_first_move(&handicaps));
+    }
+
+    #[test]
+    fn swap_handicap_hands_the_first_move_to_the_other_side() {
+        use Opponent::{Computer, User};
+        let handicaps = [Handicap::Swap];
+        // Without a swap the players take turns as usual
+        assert_eq!(
+            turn_order(&handicaps, User, Player::X, false, 3),
+            vec![
+                (User, Some(Player::X)),
+                (Computer, Some(Player::O)),
+                (User, Some(Player::X)),
+            ]
+        );
+        // The computer takes the user's X, so the user moves again with O
+        assert_eq!(
+            turn_order(&handicaps, User, Player::X, true, 4),
+            vec![
+                (User, Some(Player::X)),
+                (User, Some(Player::O)),
+                (Computer, Some(Player::X)),
+                (User, Some(Player::O)),
+            ]
+        );
+        // The user takes the computer's X, so the computer moves again with O
+        assert_eq!(
+            turn_order(&handicaps, Computer, Player::O, true, 4),
+            vec![
+                (Computer, Some(Player::X)),
+                (Computer, Some(Player::O)),
+                (User, Some(Player::X)),
+                (Computer, Some(Player::O)),
+            ]
+        );
+        // Only the first move can be swapped
+        let turn = Turn::first(User, Player::X).next(&handicaps);
+        assert!(turn.offers_swap(&handicaps));
+        assert!(!turn.swapped().next(&handicaps).offers_swap(&handicaps));
+    }
+}
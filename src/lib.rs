@@ -0,0 +1,21 @@
+// The tic-tac-toe game, its scoring, and the computer opponents that play it
+pub mod board_info;
+pub mod config;
+pub mod engine;
+pub mod game_tree;
+pub mod game_variant;
+pub mod mcts;
+pub mod morris;
+pub mod notakto;
+pub mod numerical;
+pub mod opening_book;
+pub mod order_chaos;
+pub mod quantum;
+pub mod qubic;
+pub mod scoring;
+pub mod search;
+pub mod search_tree;
+pub mod symmetry;
+pub mod tablebase;
+pub mod tic_tac_toe_board;
+pub mod ultimate;
@@ -1,12 +1,82 @@
 // This is a test to impliment the min-max algorithm for tic-tac-toe in Rust
-mod board_info;
-mod config;
-mod scoring;
-mod tic_tac_toe_board;
+use tic_tac_toe::config::{Command, Config, Variant};
+use tic_tac_toe::engine;
+use tic_tac_toe::game_tree::GameTreeStats;
+use tic_tac_toe::morris::Morris;
+use tic_tac_toe::notakto::Notakto;
+use tic_tac_toe::numerical::Numerical;
+use tic_tac_toe::opening_book::OpeningBook;
+use tic_tac_toe::order_chaos::OrderAndChaos;
+use tic_tac_toe::quantum::Quantum;
+use tic_tac_toe::qubic::Cube;
+use tic_tac_toe::search_tree::SearchTree;
+use tic_tac_toe::tablebase::Tablebase;
+use tic_tac_toe::tic_tac_toe_board::Board;
+use tic_tac_toe::ultimate::Ultimate;
 
 fn main() {
-    match config::Config::build(std::env::args()) {
-        Ok(config) => tic_tac_toe_board::Board::run(&config),
-        Err(e) => println!("{e}\n\n{}", config::Config::CLI_HELP_MESSAGE),
+    match Command::build(std::env::args()) {
+        Ok(Command::Play(config)) if config.variant == Variant::Notakto => Notakto::run(&config),
+        Ok(Command::Play(config)) if config.variant == Variant::Ultimate => Ultimate::run(&config),
+        Ok(Command::Play(config)) if config.variant == Variant::Qubic => Cube::run(&config),
+        Ok(Command::Play(config))
+            if matches!(config.variant, Variant::ThreePieces | Variant::Morris) =>
+        {
+            Morris::run(&config)
+        }
+        Ok(Command::Play(config)) if config.variant == Variant::OrderAndChaos => {
+            OrderAndChaos::run(&config)
+        }
+        Ok(Command::Play(config)) if config.variant == Variant::Numerical => {
+            Numerical::run(&config)
+        }
+        Ok(Command::Play(config)) if config.variant == Variant::Quantum => Quantum::run(&config),
+        Ok(Command::Play(config)) => Board::run(&config),
+        Ok(Command::GenerateTablebase(path)) => match Tablebase::generate_to_file(&path) {
+            Ok(()) => println!("Tablebase written to {}", path.display()),
+            Err(e) => println!("Could not write the tablebase to {}: {e}", path.display()),
+        },
+        Ok(Command::BuildBook {
+            path,
+            plies,
+            config,
+        }) => {
+            let mut computer = engine::from_config(&config);
+            let empty_board = config.starting_board();
+            let book = OpeningBook::build(&empty_board, plies, computer.as_mut(), 1);
+            match book.save(&path) {
+                Ok(()) => println!("{} positions written to {}", book.len(), path.display()),
+                Err(e) => println!(
+                    "Could not write the opening book to {}: {e}",
+                    path.display()
+                ),
+            }
+        }
+        Ok(Command::Stats(config)) => {
+            println!("{}", GameTreeStats::compute(&config.starting_board()))
+        }
+        Ok(Command::ExportDot {
+            path,
+            board,
+            player,
+            depth,
+            full_tree,
+        }) => {
+            let depth = depth.unwrap_or(board.blank_squares_remaining());
+            let tree = if full_tree {
+                SearchTree::game_tree(&board, &player, depth)
+            } else {
+                SearchTree::alpha_beta(&board, &player, depth)
+            };
+            match std::fs::write(&path, tree.to_dot()) {
+                Ok(()) => println!(
+                    "{} positions written to {}",
+                    tree.nodes.len(),
+                    path.display()
+                ),
+                Err(e) => println!("Could not write the tree to {}: {e}", path.display()),
+            }
+        }
+        Err(e) => println!("{e}\n\n{}", Config::CLI_HELP_MESSAGE),
     }
 }
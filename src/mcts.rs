@@ -0,0 +1,433 @@
+use crate::board_info::Player;
+use crate::engine::{ComputerPlayer, SearchReport};
+use crate::game_variant::{GameVariant, Position, Report, VariantEngine, WIN};
+use crate::search::{PrincipalVariation, SearchStats};
+use crate::tic_tac_toe_board::Board;
+use std::time::{Duration, Instant};
+
+/// A small pseudo-random number generator ([SplitMix64][1]). It is more than good enough for
+/// picking playout moves and, unlike seeding from the clock, lets a search be reproduced exactly.
+///
+/// [1]: https://prng.di.unimi.it/splitmix64.c
+#[derive(Debug, Clone)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Seed the generator from the system clock for when reproducibility isn't needed
+    pub fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A random index in the range `0..len`. `len` must not be zero.
+    pub fn below(&mut self, len: usize) -> usize {
+        (self.next_u64() % len as u64) as usize
+    }
+}
+
+/// How the search rated one of the moves available at the root position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RootMoveStats<M> {
+    pub player_move: M,
+    pub visits: u32,
+    /// The average playout result for the player making the move, where a win counts as 1, a
+    /// draw as 0.5, and a loss as 0
+    pub win_rate: f64,
+}
+
+impl<M: std::fmt::Display> std::fmt::Display for RootMoveStats<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} visits, {:.1}% win rate",
+            self.player_move,
+            self.visits,
+            self.win_rate * 100.0
+        )
+    }
+}
+
+/// The outcome of a single Monte Carlo Tree Search
+#[derive(Debug, Clone)]
+pub struct MctsResult<M> {
+    /// The most visited root move. This is `None` only when the root position is already over.
+    pub best_move: Option<M>,
+    /// Every expanded root move, most visited first
+    pub root_moves: Vec<RootMoveStats<M>>,
+    pub iterations: u32,
+    /// The number of positions in the search tree
+    pub tree_size: usize,
+    /// The number of plies below the root of the deepest position in the search tree
+    pub max_depth: u32,
+    /// The most visited line of play through the search tree, starting with `best_move`
+    pub principal_variation: Vec<M>,
+}
+
+// A single position in the search tree. Nodes live in a `Vec` and refer to each other by index.
+struct Node<M> {
+    player_move: Option<M>, // The move that led to this node (`None` for the root)
+    mover: Player,          // Who played `player_move`, and so is credited with the rewards
+    parent: Option<usize>,
+    depth: u32,
+    children: Vec<usize>,
+    untried_moves: Vec<M>,
+    visits: u32,
+    reward: f64,
+}
+
+impl<M> Node<M> {
+    fn new<G: GameVariant<Move = M>>(
+        player_move: Option<M>,
+        mover: Player,
+        parent: Option<(usize, u32)>,
+        game: &G,
+    ) -> Self {
+        Self {
+            player_move,
+            mover,
+            parent: parent.map(|(index, _)| index),
+            depth: parent.map_or(0, |(_, depth)| depth + 1),
+            children: Vec::new(),
+            untried_moves: game.legal_moves(),
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+}
+
+/// A Monte Carlo Tree Search computer opponent using the [UCT][1] selection rule and uniformly
+/// random playouts. Rather than searching the whole game tree like `alpha_beta()`, it repeatedly
+/// plays random games from the current position and concentrates on the moves that have done well
+/// so far. This makes it usable on boards that are far too large for an exhaustive search.
+///
+/// It searches any `GameVariant`, crediting each player at the end of a playout with
+/// `GameVariant::score()` scaled to between 0 (a loss) and 1 (a win).
+///
+/// The search stops after `iterations` playouts or once `time_limit` has elapsed, whichever comes
+/// first. Without a time limit, two searches started from the same seed give identical results.
+///
+/// [1]: https://en.wikipedia.org/wiki/Monte_Carlo_tree_search#Exploration_and_exploitation
+pub struct Mcts {
+    iterations: u32,
+    time_limit: Option<Duration>,
+    exploration: f64,
+    rng: SplitMix64,
+}
+
+impl Mcts {
+    /// The exploration constant suggested by the original UCT analysis
+    pub const DEFAULT_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+    pub fn new(iterations: u32, time_limit: Option<Duration>, seed: Option<u64>) -> Self {
+        Self {
+            iterations: iterations.max(1),
+            time_limit,
+            exploration: Self::DEFAULT_EXPLORATION,
+            rng: seed.map_or_else(SplitMix64::from_time, SplitMix64::new),
+        }
+    }
+
+    /// Run a search from `game` for the player to move
+    pub fn search<G: GameVariant>(&mut self, game: &G) -> MctsResult<G::Move> {
+        let start = Instant::now();
+        let mut tree = vec![Node::new(None, game.to_move().other(), None, game)];
+        let mut iterations = 0;
+
+        loop {
+            let mut node = 0;
+            let mut position = game.clone();
+
+            // Selection: walk down the fully expanded part of the tree
+            while tree[node].untried_moves.is_empty() && !tree[node].children.is_empty() {
+                node = self.select_child(&tree, node);
+                position
+                    .play(tree[node].player_move.as_ref().unwrap())
+                    .expect("tree moves are always legal in their parent position");
+            }
+
+            // Expansion: add one untried move as a new leaf
+            if !tree[node].untried_moves.is_empty() {
+                let index = self.rng.below(tree[node].untried_moves.len());
+                let player_move = tree[node].untried_moves.swap_remove(index);
+                let mover = position.to_move();
+                position
+                    .play(&player_move)
+                    .expect("untried moves are always legal");
+                let child = tree.len();
+                tree.push(Node::new(
+                    Some(player_move),
+                    mover,
+                    Some((node, tree[node].depth)),
+                    &position,
+                ));
+                tree[node].children.push(child);
+                node = child;
+            }
+
+            // Simulation: play randomly until the game is over
+            let outcome = self.playout(position);
+
+            // Backpropagation: credit every node on the path from the point of view of its mover
+            let mut current = Some(node);
+            while let Some(index) = current {
+                let visited = &mut tree[index];
+                visited.visits += 1;
+                visited.reward += Self::reward(&outcome, &visited.mover);
+                current = visited.parent;
+            }
+
+            iterations += 1;
+            let out_of_time = self
+                .time_limit
+                .is_some_and(|limit| start.elapsed() >= limit);
+            if iterations >= self.iterations || out_of_time {
+                break;
+            }
+        }
+
+        let mut root_moves: Vec<RootMoveStats<G::Move>> = tree[0]
+            .children
+            .iter()
+            .map(|&child| RootMoveStats {
+                player_move: tree[child].player_move.clone().unwrap(),
+                visits: tree[child].visits,
+                win_rate: tree[child].reward / f64::from(tree[child].visits),
+            })
+            .collect();
+        // Ties are left in the order the moves were expanded, which the seed decides
+        root_moves.sort_by(|a, b| {
+            b.visits
+                .cmp(&a.visits)
+                .then(b.win_rate.total_cmp(&a.win_rate))
+        });
+
+        // Follow the most visited child from the root for as long as the tree goes
+        let mut principal_variation = Vec::new();
+        let mut node = 0;
+        while let Some(&child) = tree[node]
+            .children
+            .iter()
+            .max_by_key(|&&child| tree[child].visits)
+        {
+            principal_variation.push(tree[child].player_move.clone().unwrap());
+            node = child;
+        }
+
+        MctsResult {
+            best_move: root_moves.first().map(|stats| stats.player_move.clone()),
+            root_moves,
+            iterations,
+            tree_size: tree.len(),
+            max_depth: tree.iter().map(|node| node.depth).max().unwrap_or(0),
+            principal_variation,
+        }
+    }
+
+    // Pick the child with the highest upper confidence bound
+    fn select_child<M>(&self, tree: &[Node<M>], node: usize) -> usize {
+        let parent_visits_ln = f64::from(tree[node].visits).ln();
+        let uct = |child: usize| {
+            let visits = f64::from(tree[child].visits);
+            tree[child].reward / visits + self.exploration * (parent_visits_ln / visits).sqrt()
+        };
+        tree[node]
+            .children
+            .iter()
+            .copied()
+            .max_by(|&a, &b| uct(a).total_cmp(&uct(b)))
+            .unwrap()
+    }
+
+    fn playout<G: GameVariant>(&mut self, mut game: G) -> G {
+        while !game.is_over() {
+            let mut legal_moves = game.legal_moves();
+            let player_move = legal_moves.swap_remove(self.rng.below(legal_moves.len()));
+            game.play(&player_move)
+                .expect("playout moves are always legal");
+        }
+        game
+    }
+
+    // The value of a finished game for `player`
+    fn reward<G: GameVariant>(outcome: &G, player: &Player) -> f64 {
+        let score = f64::from(outcome.score()) / f64::from(WIN);
+        let score = if outcome.to_move() == *player {
+            score
+        } else {
+            -score
+        };
+        (1.0 + score) / 2.0
+    }
+}
+
+impl<G: GameVariant> VariantEngine<G> for Mcts {
+    fn choose_move(&mut self, game: &G) -> Report<G::Move> {
+        let start = Instant::now();
+        let result = self.search(game);
+        Report {
+            player_move: result
+                .best_move
+                .expect("the computer is only asked to move while the game is still being played"),
+            score: None,
+            line: result.principal_variation,
+            nodes: result.tree_size as u64,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+/// Searches the `Position` of `board` with `player` to move
+impl ComputerPlayer for Mcts {
+    fn choose_move(&mut self, board: &Board, player: &Player) -> SearchReport {
+        let start = Instant::now();
+        let result = self.search(&Position::new(board.clone(), *player));
+        let best_move = result
+            .best_move
+            .expect("the computer is only asked to move while the game is still being played");
+        SearchReport {
+            player_move: best_move.point,
+            piece: best_move.piece,
+            move_score_turns: None,
+            stats: SearchStats {
+                nodes: result.tree_size as u64,
+                cutoffs: 0,
+                max_depth: result.max_depth,
+            },
+            elapsed: start.elapsed(),
+            principal_variation: PrincipalVariation {
+                player: *player,
+                moves: result
+                    .principal_variation
+                    .into_iter()
+                    .map(|placement| (placement.point, placement.piece))
+                    .collect(),
+                score: None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_info::Point;
+    use crate::qubic::{Cell, Cube};
+    use crate::scoring::MoveScoreTurns;
+    use crate::search::SearchContext;
+
+    // The exact value of playing `player_move`, as determined by the full alpha-beta search
+    fn exact_value(board: &Board, player: &Player, player_move: &Point) -> MoveScoreTurns {
+        let mut child = board.clone();
+        child.insert(player_move, player.square_type()).unwrap();
+        child.alpha_beta(
+            &player.other(),
+            child.blank_squares_remaining(),
+            &MoveScoreTurns::worst_for(&player.other()),
+            &MoveScoreTurns::worst_for(player),
+            &mut SearchContext::new(true),
+        )
+    }
+
+    #[test]
+    fn takes_immediate_win() {
+        let board = Board::from_string(
+            "X X B |
+             O O B |
+             B B B",
+        );
+        let result = Mcts::new(2_000, None, Some(1)).search(&Position::new(board, Player::X));
+        assert_eq!(result.best_move.unwrap().point, Point { x: 0, y: 2 });
+    }
+
+    #[test]
+    fn blocks_opponent_win() {
+        let board = Board::from_string(
+            "X B B |
+             O O B |
+             X B B",
+        );
+        let result = Mcts::new(5_000, None, Some(2)).search(&Position::new(board, Player::X));
+        assert_eq!(result.best_move.unwrap().point, Point { x: 1, y: 2 });
+    }
+
+    #[test]
+    fn same_seed_gives_same_search() {
+        let game = Position::new(Board::initialize_blank_board(3), Player::X);
+        let first = Mcts::new(1_000, None, Some(42)).search(&game);
+        let second = Mcts::new(1_000, None, Some(42)).search(&game);
+        assert_eq!(first.best_move, second.best_move);
+        assert_eq!(first.root_moves, second.root_moves);
+    }
+
+    #[test]
+    fn root_visits_add_up_to_iterations() {
+        let game = Position::new(Board::initialize_blank_board(3), Player::X);
+        let result = Mcts::new(500, None, Some(7)).search(&game);
+        assert_eq!(result.iterations, 500);
+        assert_eq!(result.root_moves.len(), 9);
+        assert_eq!(
+            result
+                .root_moves
+                .iter()
+                .map(|stats| stats.visits)
+                .sum::<u32>(),
+            500
+        );
+    }
+
+    #[test]
+    fn agrees_with_alpha_beta_on_3x3() {
+        let positions = [
+            ("X B B | B O B | B B B", Player::X),
+            ("X B B | B O B | B B X", Player::O),
+            ("X O B | B X B | B B B", Player::O),
+            ("O B B | B X B | B B X", Player::O),
+        ];
+        for (position, player) in positions {
+            let board = Board::from_string(position);
+            let best = board.alpha_beta(
+                &player,
+                board.blank_squares_remaining(),
+                &MoveScoreTurns::worst_for(&player),
+                &MoveScoreTurns::worst_for(&player.other()),
+                &mut SearchContext::new(true),
+            );
+            let chosen = Mcts::new(20_000, None, Some(3))
+                .search(&Position::new(board.clone(), player))
+                .best_move
+                .unwrap()
+                .point;
+            assert_eq!(
+                exact_value(&board, &player, &chosen).score,
+                best.score,
+                "{position}: MCTS chose {chosen}"
+            );
+        }
+    }
+
+    #[test]
+    fn searches_other_games_too() {
+        // On a flat 4x4 layer of qubic, X completes the top row rather than let O finish theirs
+        let mut cube = Cube::with_dimensions(4, 2, Player::X);
+        for y in 0..3 {
+            cube.play(&[0, y]).unwrap();
+            cube.play(&[1, y]).unwrap();
+        }
+        let result = Mcts::new(2_000, None, Some(5)).search(&cube);
+        assert_eq!(result.best_move, Some(Cell(vec![0, 3])));
+    }
+}
@@ -0,0 +1,608 @@
+use crate::board_info::{Player, Point};
+use crate::config::{Config, Variant};
+use crate::game_variant::{self, GameVariant, Report, VariantEngine, WIN};
+use crate::scoring::GameStatus;
+use crate::symmetry::canonical_position;
+use crate::tic_tac_toe_board::Board;
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+/// A move in a game with three pieces each: placing a new piece while a player still has some in
+/// hand, or moving one already on the board once they are all placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MorrisMove {
+    Place(Point),
+    Slide { from: Point, to: Point },
+}
+
+/// Written the way moves are entered, e.g. "1 1" or "0 0 0 1"
+impl std::fmt::Display for MorrisMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Place(point) => write!(f, "{} {}", point.x, point.y),
+            Self::Slide { from, to } => write!(f, "{} {} {} {}", from.x, from.y, to.x, to.y),
+        }
+    }
+}
+
+/// A position of tic-tac-toe with three pieces each, or of Three Men's Morris. Both players place
+/// their three pieces as in tic-tac-toe, and after that a turn moves one of them: to any blank
+/// square, or in Three Men's Morris only to a neighbouring one along a row, column, or diagonal.
+/// The pieces live on an ordinary `Board`, so it finds the lines. A player who can't move loses.
+///
+/// Since pieces can move back and forth a game can go on forever; see `History` for the rules
+/// that end it.
+#[derive(Debug, Clone)]
+pub struct Morris {
+    board: Board,
+    to_move: Player,
+    /// Whether pieces can only move to a neighbouring square (Three Men's Morris)
+    adjacent_only: bool,
+}
+
+impl Morris {
+    pub const SIZE: usize = 3;
+    /// How many pieces each player has
+    pub const PIECES: usize = 3;
+
+    /// The empty board with `first` to move
+    pub fn new(adjacent_only: bool, first: Player) -> Self {
+        Self {
+            board: Board::initialize_blank_board(Self::SIZE),
+            to_move: first,
+            adjacent_only,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    /// How many pieces `player` still has to place
+    pub fn pieces_in_hand(&self, player: &Player) -> usize {
+        let on_board = self
+            .squares()
+            .filter(|point| self.board.square(point) == player.square_type())
+            .count();
+        Self::PIECES - on_board
+    }
+
+    /// A line wins for whoever made it, and a player with no legal move loses
+    pub fn game_status(&self) -> GameStatus {
+        match self.board.game_status() {
+            GameStatus::StillPlaying if self.legal_moves().is_empty() => match self.to_move {
+                Player::X => GameStatus::OWin,
+                Player::O => GameStatus::XWin,
+            },
+            game_status => game_status,
+        }
+    }
+
+    /// Every move the player to move can make, in a stable order
+    pub fn legal_moves(&self) -> Vec<MorrisMove> {
+        let blank_squares = self.board.blank_squares();
+        if self.pieces_in_hand(&self.to_move) > 0 {
+            return blank_squares.into_iter().map(MorrisMove::Place).collect();
+        }
+        self.squares()
+            .filter(|from| self.board.square(from) == self.to_move.square_type())
+            .flat_map(|from| {
+                blank_squares
+                    .iter()
+                    .filter(move |to| !self.adjacent_only || Self::adjacent(&from, to))
+                    .map(move |to| MorrisMove::Slide { from, to: *to })
+            })
+            .collect()
+    }
+
+    /// Play `player_move` for the player to move
+    pub fn play(&mut self, player_move: &MorrisMove) -> Result<(), String> {
+        let player = self.to_move;
+        match (player_move, self.pieces_in_hand(&player)) {
+            (MorrisMove::Place(point), 0) => {
+                return Err(format!(
+                    "All of your pieces are on the board, so move one of them instead of placing at {point}"
+                ))
+            }
+            (MorrisMove::Place(point), _) => self.board.insert(point, player.square_type())?,
+            (MorrisMove::Slide { .. }, in_hand) if in_hand > 0 => {
+                return Err(format!(
+                    "Place your {in_hand} remaining pieces before moving any"
+                ))
+            }
+            (MorrisMove::Slide { from, to }, _) => {
+                if self.board.square(from) != player.square_type() {
+                    return Err(format!("There is no {player} piece at {from} to move"));
+                }
+                if self.adjacent_only && !Self::adjacent(from, to) {
+                    return Err(format!(
+                        "Pieces can only move to a neighbouring square, and {to} is not next to {from}"
+                    ));
+                }
+                if !self.board.blank_squares().contains(to) {
+                    return Err(format!("Not a blank square: {to}"));
+                }
+                self.board.remove(from)?;
+                self.board.insert(to, player.square_type())?;
+            }
+        }
+        self.to_move = player.other();
+        Ok(())
+    }
+
+    // The position with the player to move. Rotating or reflecting the board changes neither
+    // which squares are neighbours nor who wins, so the canonical position is used.
+    fn key(&self) -> (String, Player) {
+        (canonical_position(&self.board).0, self.to_move)
+    }
+
+    fn squares(&self) -> impl Iterator<Item = Point> {
+        (0..Self::SIZE).flat_map(|x| (0..Self::SIZE).map(move |y| Point { x, y }))
+    }
+
+    // Neighbours are next to each other in a row or column, or along one of the two diagonals
+    fn adjacent(a: &Point, b: &Point) -> bool {
+        let n = Self::SIZE - 1;
+        a != b
+            && a.x.abs_diff(b.x) <= 1
+            && a.y.abs_diff(b.y) <= 1
+            && (a.x == b.x
+                || a.y == b.y
+                || (a.x == a.y && b.x == b.y)
+                || (a.x + a.y == n && b.x + b.y == n))
+    }
+}
+
+/// The positions a game has been through, for the rules that stop it from going on forever: it is
+/// a draw once the same position comes up for the third time with the same player to move, or
+/// once `MOVE_LIMIT` moves have been played
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    seen: HashMap<(String, Player), u32>,
+    /// How many positions have been recorded, counting the starting one
+    positions: u32,
+}
+
+impl History {
+    pub const REPETITIONS: u32 = 3;
+    pub const MOVE_LIMIT: u32 = 100;
+
+    /// Record the position `game` has reached. If that makes the game a draw, returns why.
+    pub fn record(&mut self, game: &Morris) -> Option<String> {
+        let repeated = self
+            .seen
+            .entry((game.board.position(), game.to_move))
+            .or_default();
+        *repeated += 1;
+        self.positions += 1;
+        let moves = self.positions - 1;
+        if *repeated >= Self::REPETITIONS {
+            Some(format!("the same position came up {repeated} times"))
+        } else if moves >= Self::MOVE_LIMIT {
+            Some(format!("{moves} moves were played"))
+        } else {
+            None
+        }
+    }
+}
+
+/// A game of `Morris` as a `GameVariant`: the position along with its `History`, so the game
+/// ends in a draw when the history says so
+#[derive(Debug, Clone)]
+pub struct MorrisGame {
+    position: Morris,
+    history: History,
+    /// Why the game was drawn, once it has been
+    draw: Option<String>,
+}
+
+impl MorrisGame {
+    /// The empty board with `first` to move
+    pub fn new(adjacent_only: bool, first: Player) -> Self {
+        let position = Morris::new(adjacent_only, first);
+        let mut history = History::default();
+        let draw = history.record(&position);
+        Self {
+            position,
+            history,
+            draw,
+        }
+    }
+
+    pub fn position(&self) -> &Morris {
+        &self.position
+    }
+}
+
+impl std::fmt::Display for MorrisGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.position.board())
+    }
+}
+
+impl GameVariant for MorrisGame {
+    type Move = MorrisMove;
+
+    fn to_move(&self) -> Player {
+        self.position.to_move()
+    }
+
+    fn legal_moves(&self) -> Vec<MorrisMove> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        self.position.legal_moves()
+    }
+
+    fn play(&mut self, player_move: &MorrisMove) -> Result<(), String> {
+        if self.is_over() {
+            return Err("The game is over".to_string());
+        }
+        self.position.play(player_move)?;
+        self.draw = self.history.record(&self.position);
+        Ok(())
+    }
+
+    fn is_over(&self) -> bool {
+        self.draw.is_some() || self.position.game_status() != GameStatus::StillPlaying
+    }
+
+    fn score(&self) -> i32 {
+        if self.draw.is_some() {
+            return 0;
+        }
+        match (self.position.game_status(), self.to_move()) {
+            (GameStatus::XWin, Player::X) | (GameStatus::OWin, Player::O) => WIN,
+            (GameStatus::XWin, Player::O) | (GameStatus::OWin, Player::X) => -WIN,
+            (GameStatus::Draw | GameStatus::StillPlaying, _) => 0,
+        }
+    }
+
+    // A move is "x y" while placing and "from_x from_y to_x to_y" once all pieces are placed
+    fn parse_move(&self, input: &str) -> Result<MorrisMove, String> {
+        let numbers = input
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|e| e.to_string())?;
+        match numbers[..] {
+            [x, y] => Ok(MorrisMove::Place(Point { x, y })),
+            [from_x, from_y, to_x, to_y] => Ok(MorrisMove::Slide {
+                from: Point {
+                    x: from_x,
+                    y: from_y,
+                },
+                to: Point { x: to_x, y: to_y },
+            }),
+            _ => Err(format!(
+                "Incorrect number of arguments. Expected 2 (x y) or 4 (from_x from_y to_x to_y). Got {}",
+                numbers.len()
+            )),
+        }
+    }
+
+    fn note(&self) -> Option<String> {
+        let mover = self.to_move();
+        Some(match self.position.pieces_in_hand(&mover) {
+            0 => format!("{mover} moves a piece"),
+            in_hand => format!("{mover} has {in_hand} pieces left to place"),
+        })
+    }
+
+    fn status(&self) -> String {
+        match &self.draw {
+            Some(reason) => format!("Draw, because {reason}"),
+            None => format!("{:?}", self.position.game_status()),
+        }
+    }
+}
+
+/// Who wins a position with perfect play, from the point of view of the player to move, and how
+/// many more moves it takes. Positions that neither player can force a win from are draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win(u32),
+    Draw,
+    Loss(u32),
+}
+
+impl Outcome {
+    // The outcome for the player who moved into a position with this outcome
+    fn before_move(&self) -> Outcome {
+        match self {
+            Outcome::Win(moves) => Outcome::Loss(moves + 1),
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Loss(moves) => Outcome::Win(moves + 1),
+        }
+    }
+
+    // Winning beats drawing, which beats losing. Winning sooner or losing later is better.
+    fn is_better_than(&self, other: &Outcome) -> bool {
+        let rank = |outcome: &Outcome| match outcome {
+            Outcome::Win(moves) => (2, -i64::from(*moves)),
+            Outcome::Draw => (1, 0),
+            Outcome::Loss(moves) => (0, i64::from(*moves)),
+        };
+        rank(self) > rank(other)
+    }
+}
+
+impl Outcome {
+    // The outcome as a `Report` score: wins found sooner and losses put off longer score
+    // further from 0, as they do in `Negamax`
+    fn score(&self) -> i32 {
+        match self {
+            Outcome::Win(moves) => WIN - *moves as i32,
+            Outcome::Draw => 0,
+            Outcome::Loss(moves) => -WIN + *moves as i32,
+        }
+    }
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Win(moves) => write!(f, "the player to move wins in {moves} moves"),
+            Outcome::Draw => write!(f, "a draw"),
+            Outcome::Loss(moves) => write!(f, "the player to move loses in {moves} moves"),
+        }
+    }
+}
+
+/// Plays perfectly by solving every position of the game up front. A search that looks ahead
+/// from the current position could go round in circles forever, so instead the solver works
+/// backwards from the finished games (retrograde analysis): a position is won if some move leads
+/// to a lost position, and lost if every move leads to a won one. Whatever is left once nothing
+/// more can be decided is a draw, since neither player can force the game out of it.
+#[derive(Debug)]
+pub struct MorrisSolver {
+    solved: HashMap<(String, Player), Outcome>,
+}
+
+impl MorrisSolver {
+    pub fn new(adjacent_only: bool) -> Self {
+        // Every position that can be reached, and the positions its moves lead to
+        let mut children: HashMap<(String, Player), Vec<(String, Player)>> = HashMap::new();
+        let mut solved = HashMap::new();
+        let mut queue = VecDeque::from([
+            Morris::new(adjacent_only, Player::X),
+            Morris::new(adjacent_only, Player::O),
+        ]);
+        while let Some(game) = queue.pop_front() {
+            let key = game.key();
+            if children.contains_key(&key) || solved.contains_key(&key) {
+                continue;
+            }
+            if game.game_status() != GameStatus::StillPlaying {
+                // The player to move lost to a line or has no moves left
+                solved.insert(key, Outcome::Loss(0));
+                continue;
+            }
+            let mut next = Vec::new();
+            for player_move in game.legal_moves() {
+                let mut new_game = game.clone();
+                let _ = new_game.play(&player_move);
+                next.push(new_game.key());
+                queue.push_back(new_game);
+            }
+            children.insert(key, next);
+        }
+
+        // Positions decided after n rounds are won or lost in n moves, since a round only looks
+        // at the outcomes the rounds before it decided
+        loop {
+            let decided: Vec<_> = children
+                .iter()
+                .filter(|(key, _)| !solved.contains_key(*key))
+                .filter_map(|(key, next)| {
+                    let outcomes: Vec<Option<&Outcome>> =
+                        next.iter().map(|child| solved.get(child)).collect();
+                    let fastest_win = outcomes
+                        .iter()
+                        .filter_map(|outcome| match outcome {
+                            Some(Outcome::Loss(moves)) => Some(moves + 1),
+                            _ => None,
+                        })
+                        .min();
+                    let slowest_loss = outcomes
+                        .iter()
+                        .map(|outcome| match outcome {
+                            Some(Outcome::Win(moves)) => Some(moves + 1),
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<u32>>>()
+                        .and_then(|moves| moves.into_iter().max());
+                    match (fastest_win, slowest_loss) {
+                        (Some(moves), _) => Some((key.clone(), Outcome::Win(moves))),
+                        (None, Some(moves)) => Some((key.clone(), Outcome::Loss(moves))),
+                        (None, None) => None,
+                    }
+                })
+                .collect();
+            if decided.is_empty() {
+                break;
+            }
+            solved.extend(decided);
+        }
+        Self { solved }
+    }
+
+    /// Who wins `game` with perfect play
+    pub fn solve(&self, game: &Morris) -> Outcome {
+        self.solved
+            .get(&game.key())
+            .copied()
+            .unwrap_or(Outcome::Draw)
+    }
+
+    /// The best move in `game`, which must not be over, and the outcome it leads to
+    pub fn best_move(&self, game: &Morris) -> (MorrisMove, Outcome) {
+        let mut best: Option<(MorrisMove, Outcome)> = None;
+        for player_move in game.legal_moves() {
+            let mut new_game = game.clone();
+            let _ = new_game.play(&player_move);
+            let outcome = self.solve(&new_game).before_move();
+            if best.is_none_or(|(_, best)| outcome.is_better_than(&best)) {
+                best = Some((player_move, outcome));
+            }
+        }
+        best.expect("a game that isn't over has a legal move")
+    }
+}
+
+impl VariantEngine<MorrisGame> for MorrisSolver {
+    fn choose_move(&mut self, game: &MorrisGame) -> Report<MorrisMove> {
+        let start = Instant::now();
+        let (player_move, outcome) = self.best_move(game.position());
+        // A won game is played out to its end. Drawn games could go round forever, so their line
+        // stops after the first move.
+        let mut line = vec![player_move];
+        if outcome != Outcome::Draw {
+            let mut position = game.position().clone();
+            let _ = position.play(&player_move);
+            while position.game_status() == GameStatus::StillPlaying {
+                let (next, _) = self.best_move(&position);
+                let _ = position.play(&next);
+                line.push(next);
+            }
+        }
+        Report {
+            player_move,
+            line,
+            score: Some(outcome.score()),
+            nodes: game.legal_moves().len() as u64,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+#[rustfmt::skip]
+impl Morris {
+    const INTRO_MESSAGE: &'static str =
+        "\nWelcome to Tic-Tac-Toe with Three Pieces!\
+         \n-----------------------------------------\n\
+         \n\
+         Each player has only three pieces. Place them by entering the coordinates of\n\
+         a square (e.g., \"0 1\"). Once they are all on the board, move one of them\n\
+         instead by entering the coordinates of the piece and of the blank square it\n\
+         moves to (e.g., \"0 1 1 1\"). Enter \"hint\" to see the computer's suggestion.\n\
+         \n\
+         The same position coming up three times, or a hundred moves, is a draw.\n\
+         \n\
+         ##################################################\n";
+
+    const MORRIS_MESSAGE: &'static str =
+        "Three Men's Morris: pieces can only move to a neighbouring square along a\n\
+         row, column, or diagonal, and a player who can't move loses.\n\
+         \n\
+         ##################################################\n";
+}
+
+impl Morris {
+    /// Play tic-tac-toe with three pieces, or Three Men's Morris, against the computer. The
+    /// computer always plays perfectly.
+    pub fn run(config: &Config) {
+        let adjacent_only = config.variant == Variant::Morris;
+        let mut solver = MorrisSolver::new(adjacent_only);
+        let intro = if adjacent_only {
+            format!("{}\n{}", Self::INTRO_MESSAGE, Self::MORRIS_MESSAGE)
+        } else {
+            Self::INTRO_MESSAGE.to_string()
+        };
+        let new_game = |first, _| MorrisGame::new(adjacent_only, first);
+        game_variant::run(config, &intro, new_game, &mut solver);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Plays the placing moves in order, alternating players
+    fn placed(adjacent_only: bool, points: &[(usize, usize)]) -> Morris {
+        let mut game = Morris::new(adjacent_only, Player::X);
+        for (x, y) in points {
+            game.play(&MorrisMove::Place(Point { x: *x, y: *y }))
+                .unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn pieces_slide_once_they_are_all_placed() {
+        let mut game = placed(true, &[(0, 0), (0, 1), (1, 2), (1, 1), (2, 1), (2, 2)]);
+        assert_eq!(game.pieces_in_hand(&Player::X), 0);
+        assert!(game.play(&MorrisMove::Place(Point { x: 0, y: 2 })).is_err());
+
+        // (0, 0) is next to (1, 0) but not to (2, 0), and O's piece can't be moved by X
+        let slide = |from: (usize, usize), to: (usize, usize)| MorrisMove::Slide {
+            from: Point {
+                x: from.0,
+                y: from.1,
+            },
+            to: Point { x: to.0, y: to.1 },
+        };
+        assert!(game.play(&slide((0, 0), (2, 0))).is_err());
+        assert!(game.play(&slide((1, 1), (1, 0))).is_err());
+        assert!(game
+            .legal_moves()
+            .iter()
+            .all(|player_move| matches!(player_move, MorrisMove::Slide { .. })));
+        game.play(&slide((0, 0), (1, 0))).unwrap();
+        assert_eq!(game.board().position(), "BOB/XOX/BXO");
+        assert_eq!(game.to_move(), Player::O);
+
+        // Without the neighbour rule a piece can go to any blank square
+        let mut game = placed(false, &[(0, 0), (0, 1), (1, 2), (1, 1), (2, 1), (2, 2)]);
+        game.play(&slide((0, 0), (2, 0))).unwrap();
+    }
+
+    #[test]
+    fn repeating_a_position_three_times_is_a_draw() {
+        let mut game = MorrisGame::new(true, Player::X);
+        for (x, y) in [(0, 0), (0, 1), (1, 2), (1, 1), (2, 1), (2, 2)] {
+            GameVariant::play(&mut game, &MorrisMove::Place(Point { x, y })).unwrap();
+        }
+        // X and O each step away and back twice
+        for _ in 0..2 {
+            for (from, to) in [
+                (Point { x: 0, y: 0 }, Point { x: 1, y: 0 }),
+                (Point { x: 0, y: 1 }, Point { x: 0, y: 2 }),
+                (Point { x: 1, y: 0 }, Point { x: 0, y: 0 }),
+                (Point { x: 0, y: 2 }, Point { x: 0, y: 1 }),
+            ] {
+                assert!(!game.is_over());
+                GameVariant::play(&mut game, &MorrisMove::Slide { from, to }).unwrap();
+            }
+        }
+        assert!(game.is_over());
+        assert_eq!(game.score(), 0);
+        assert!(game.legal_moves().is_empty());
+        assert!(game.status().starts_with("Draw"));
+    }
+
+    #[test]
+    fn first_player_wins_three_mens_morris_from_the_centre() {
+        let mut solver = MorrisSolver::new(true);
+        let game = Morris::new(true, Player::X);
+        assert!(matches!(solver.solve(&game), Outcome::Win(_)));
+        let (player_move, _) = solver.best_move(&game);
+        assert_eq!(player_move, MorrisMove::Place(Point { x: 1, y: 1 }));
+
+        let report = solver.choose_move(&MorrisGame::new(true, Player::X));
+        assert_eq!(report.player_move, player_move);
+        assert!(report.score.unwrap() > WIN / 2);
+
+        // The hint's line is played out until X wins
+        let mut game = game;
+        for player_move in &report.line {
+            game.play(player_move).unwrap();
+        }
+        assert_eq!(game.game_status(), GameStatus::XWin);
+        assert_eq!(report.line.len() as i32, WIN - report.score.unwrap());
+    }
+}
@@ -0,0 +1,491 @@
+use crate::board_info::{Player, Point, SquareType};
+use crate::config::Config;
+use crate::game_variant::{self, GameVariant, Report, VariantEngine, WIN};
+use crate::scoring::GameStatus;
+use crate::symmetry::Symmetry;
+use crate::tic_tac_toe_board::Board;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// A move in Notakto: an X at `point` on board number `board`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotaktoMove {
+    pub board: usize,
+    pub point: Point,
+}
+
+/// Written the way moves are entered, e.g. "1 0 2"
+impl std::fmt::Display for NotaktoMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.board, self.point.x, self.point.y)
+    }
+}
+
+/// A game of Notakto: both players place X on any of several boards, a board is dead once it
+/// contains a line, and whoever kills the last board loses. Each board is an ordinary `Board`
+/// that only ever has X placed on it, so it finds its own lines.
+///
+/// The players are still told apart as `Player::X` and `Player::O`, even though neither of them
+/// places an O.
+#[derive(Debug, Clone)]
+pub struct Notakto {
+    boards: Vec<Board>,
+    to_move: Player,
+}
+
+/// Who wins a Notakto position with perfect play, from the point of view of the player to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub mover_wins: bool,
+    /// How many more moves the game lasts
+    pub moves: u32,
+}
+
+impl Outcome {
+    // Whether this is better for the player to move than `other`: winning beats losing, and then
+    // winning sooner or losing later is better
+    fn is_better_than(&self, other: &Outcome) -> bool {
+        match (self.mover_wins, other.mover_wins) {
+            (true, false) => true,
+            (false, true) => false,
+            (true, true) => self.moves < other.moves,
+            (false, false) => self.moves > other.moves,
+        }
+    }
+}
+
+impl Outcome {
+    // The outcome as a `Report` score: wins found sooner and losses put off longer score
+    // further from 0, as they do in `Negamax`
+    fn score(&self) -> i32 {
+        if self.mover_wins {
+            WIN - self.moves as i32
+        } else {
+            -WIN + self.moves as i32
+        }
+    }
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let result = if self.mover_wins { "wins" } else { "loses" };
+        write!(f, "the player to move {result} in {} moves", self.moves)
+    }
+}
+
+impl Notakto {
+    /// The width of every board
+    pub const SIZE: usize = 3;
+
+    /// `boards` empty boards with `first` to move
+    pub fn new(boards: usize, first: Player) -> Self {
+        Self {
+            boards: vec![Board::initialize_blank_board(Self::SIZE); boards],
+            to_move: first,
+        }
+    }
+
+    pub fn boards(&self) -> &[Board] {
+        &self.boards
+    }
+
+    /// Whether `board` has a line on it and can no longer be played on
+    pub fn is_dead(board: &Board) -> bool {
+        board.game_status() != GameStatus::StillPlaying
+    }
+
+    /// Whether every board is dead, so the player who moved last lost
+    pub fn is_over(&self) -> bool {
+        self.boards.iter().all(Self::is_dead)
+    }
+
+    /// Every board and square that can be played, in a stable order
+    pub fn legal_moves(&self) -> Vec<NotaktoMove> {
+        self.boards
+            .iter()
+            .enumerate()
+            .filter(|(_, board)| !Self::is_dead(board))
+            .flat_map(|(index, board)| {
+                board
+                    .blank_squares()
+                    .into_iter()
+                    .map(move |point| NotaktoMove {
+                        board: index,
+                        point,
+                    })
+            })
+            .collect()
+    }
+
+    /// Place an X at `point` on board number `board` for the player to move
+    pub fn play(&mut self, board: usize, point: &Point) -> Result<(), String> {
+        let Some(target) = self.boards.get_mut(board) else {
+            return Err(format!(
+                "Not a valid board: {board}. Boards are numbered 0 to {}",
+                self.boards.len() - 1
+            ));
+        };
+        if Self::is_dead(target) {
+            return Err(format!("Board {board} is dead and can't be played on"));
+        }
+        target.insert(point, SquareType::X)?;
+        self.to_move = self.to_move.other();
+        Ok(())
+    }
+}
+
+impl GameVariant for Notakto {
+    type Move = NotaktoMove;
+
+    fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    fn legal_moves(&self) -> Vec<NotaktoMove> {
+        Notakto::legal_moves(self)
+    }
+
+    fn play(&mut self, player_move: &NotaktoMove) -> Result<(), String> {
+        Notakto::play(self, player_move.board, &player_move.point)
+    }
+
+    fn is_over(&self) -> bool {
+        Notakto::is_over(self)
+    }
+
+    // Once every board is dead the player to move has won, since their opponent killed the last
+    // one
+    fn score(&self) -> i32 {
+        if Notakto::is_over(self) {
+            WIN
+        } else {
+            0
+        }
+    }
+
+    // A move is "board x y"
+    fn parse_move(&self, input: &str) -> Result<NotaktoMove, String> {
+        let numbers = input
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|e| e.to_string())?;
+        match numbers[..] {
+            [board, x, y] => Ok(NotaktoMove {
+                board,
+                point: Point { x, y },
+            }),
+            _ => Err(format!(
+                "Incorrect number of arguments. Expected 3 (board x y). Got {}",
+                numbers.len()
+            )),
+        }
+    }
+
+    fn status(&self) -> String {
+        if Notakto::is_over(self) {
+            let winner = self.to_move;
+            format!(
+                "{winner}Win, because {} killed the last board",
+                winner.other()
+            )
+        } else {
+            format!("{:?}", GameStatus::StillPlaying)
+        }
+    }
+}
+
+// A board as a bit mask of the squares with an X, row by row
+fn mask(board: &Board) -> u16 {
+    (0..Notakto::SIZE * Notakto::SIZE)
+        .filter(|square| board.square(&square_point(*square)) == SquareType::X)
+        .fold(0, |mask, square| mask | 1 << square)
+}
+
+fn square_point(square: usize) -> Point {
+    Point {
+        x: square / Notakto::SIZE,
+        y: square % Notakto::SIZE,
+    }
+}
+
+/// The boards side by side, numbered, with the dead ones marked
+impl std::fmt::Display for Notakto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.boards.first().map_or(0, |board| board.size() * 4);
+        let mut lines = vec![String::new(); 1];
+        for (index, board) in self.boards.iter().enumerate() {
+            let title = if Self::is_dead(board) {
+                format!("{index} (dead)")
+            } else {
+                index.to_string()
+            };
+            let rows: Vec<String> = board.to_string().lines().map(String::from).collect();
+            lines.resize(rows.len() + 1, String::new());
+            for (line, text) in lines.iter_mut().zip(std::iter::once(title).chain(rows)) {
+                line.push_str(&format!("{text:<width$}    "));
+            }
+        }
+        let lines: Vec<&str> = lines.iter().map(|line| line.trim_end()).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Plays Notakto perfectly by solving every position it meets.
+///
+/// A 3x3 board only has 512 ways to hold X, so the solver works them all out when it is made:
+/// which have a line, and which mask (the smallest) stands for each one and its rotations and
+/// reflections. A position is then just the sorted masks of its live boards, since neither the
+/// order of the boards, their orientation, nor the dead boards change who wins. That leaves under
+/// 20,000 positions for three boards, solved in a moment and remembered for the rest of the game.
+#[derive(Debug)]
+pub struct NotaktoSolver {
+    // The canonical mask of every mask, `None` for boards with a line
+    canonical: Vec<Option<u16>>,
+    solved: HashMap<Vec<u16>, Outcome>,
+}
+
+impl NotaktoSolver {
+    pub fn new() -> Self {
+        let size = Notakto::SIZE;
+        let canonical = (0..1u16 << (size * size))
+            .map(|mask| {
+                let mut board = Board::initialize_blank_board(size);
+                for square in (0..size * size).filter(|square| mask & 1 << square != 0) {
+                    let _ = board.insert(&square_point(square), SquareType::X);
+                }
+                if Notakto::is_dead(&board) {
+                    return None;
+                }
+                Symmetry::ALL
+                    .iter()
+                    .map(|symmetry| {
+                        (0..size * size)
+                            .filter(|square| mask & 1 << square != 0)
+                            .map(|square| {
+                                let Point { x, y } = symmetry.apply(&square_point(square), size);
+                                1 << (x * size + y)
+                            })
+                            .sum()
+                    })
+                    .min()
+            })
+            .collect();
+        Self {
+            canonical,
+            solved: HashMap::new(),
+        }
+    }
+
+    /// Who wins `game` with perfect play
+    pub fn solve(&mut self, game: &Notakto) -> Outcome {
+        let mut key: Vec<u16> = game
+            .boards()
+            .iter()
+            .filter_map(|board| self.canonical[usize::from(mask(board))])
+            .collect();
+        key.sort_unstable();
+        self.solve_key(key)
+    }
+
+    // Who wins the position whose live boards have the sorted canonical masks `key`
+    fn solve_key(&mut self, key: Vec<u16>) -> Outcome {
+        if key.is_empty() {
+            // The opponent killed the last board
+            return Outcome {
+                mover_wins: true,
+                moves: 0,
+            };
+        }
+        if let Some(outcome) = self.solved.get(&key) {
+            return *outcome;
+        }
+
+        let mut best: Option<Outcome> = None;
+        for (index, &board) in key.iter().enumerate() {
+            // Boards that are the same have the same moves
+            if index > 0 && key[index - 1] == board {
+                continue;
+            }
+            for square in (0..Notakto::SIZE * Notakto::SIZE).filter(|s| board & 1 << s == 0) {
+                let mut new_key = key.clone();
+                match self.canonical[usize::from(board | 1 << square)] {
+                    Some(new_board) => new_key[index] = new_board,
+                    None => {
+                        new_key.remove(index);
+                    }
+                }
+                new_key.sort_unstable();
+                let reply = self.solve_key(new_key);
+                let outcome = Outcome {
+                    mover_wins: !reply.mover_wins,
+                    moves: reply.moves + 1,
+                };
+                if best.is_none_or(|best| outcome.is_better_than(&best)) {
+                    best = Some(outcome);
+                }
+            }
+        }
+        let outcome = best.expect("a live board has a blank square");
+        self.solved.insert(key, outcome);
+        outcome
+    }
+
+    /// The best move in `game`, which must not be over, and the outcome it leads to
+    pub fn best_move(&mut self, game: &Notakto) -> (NotaktoMove, Outcome) {
+        let mut best: Option<(NotaktoMove, Outcome)> = None;
+        for player_move in game.legal_moves() {
+            let mut new_game = game.clone();
+            let _ = new_game.play(player_move.board, &player_move.point);
+            let reply = self.solve(&new_game);
+            let outcome = Outcome {
+                mover_wins: !reply.mover_wins,
+                moves: reply.moves + 1,
+            };
+            if best.is_none_or(|(_, best)| outcome.is_better_than(&best)) {
+                best = Some((player_move, outcome));
+            }
+        }
+        best.expect("a game that isn't over has a live board")
+    }
+}
+
+impl Default for NotaktoSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VariantEngine<Notakto> for NotaktoSolver {
+    fn choose_move(&mut self, game: &Notakto) -> Report<NotaktoMove> {
+        let start = Instant::now();
+        let (player_move, outcome) = self.best_move(game);
+        // Someone always wins, so the line is played out to the end
+        let mut line = vec![player_move];
+        let mut position = game.clone();
+        let _ = position.play(player_move.board, &player_move.point);
+        while !position.is_over() {
+            let (next, _) = self.best_move(&position);
+            let _ = position.play(next.board, &next.point);
+            line.push(next);
+        }
+        Report {
+            player_move,
+            line,
+            score: Some(outcome.score()),
+            nodes: game.legal_moves().len() as u64,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+#[rustfmt::skip]
+impl Notakto {
+    const INTRO_MESSAGE: &'static str =
+        "\nWelcome to Notakto!\
+         \n-------------------\n\
+         \n\
+         Both players place X. A board is dead once it has a line of X on it, and\n\
+         whoever kills the last board LOSES.\n\
+         \n\
+         Play by entering the number of a board followed by the coordinates of the\n\
+         square you want to play in (e.g., \"1 0 2\" plays in row 0, column 2 of board 1).\n\
+         Enter \"hint\" to see the computer's suggestion.\n\
+         \n\
+         ##################################################\n";
+}
+
+impl Notakto {
+    /// Play a game of Notakto against the computer. The computer always plays perfectly.
+    pub fn run(config: &Config) {
+        let mut solver = NotaktoSolver::new();
+        let boards = config.boards;
+        let new_game = |first, _| Notakto::new(boards, first);
+        game_variant::run(config, Self::INTRO_MESSAGE, new_game, &mut solver);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boards_die_when_they_get_a_line() {
+        let mut game = Notakto::new(2, Player::X);
+        for y in 0..2 {
+            game.play(0, &Point { x: 0, y }).unwrap();
+        }
+        assert!(!Notakto::is_dead(&game.boards()[0]));
+        game.play(0, &Point { x: 0, y: 2 }).unwrap();
+        assert!(Notakto::is_dead(&game.boards()[0]));
+        assert!(game.play(0, &Point { x: 2, y: 2 }).is_err());
+        assert!(game
+            .legal_moves()
+            .iter()
+            .all(|player_move| player_move.board == 1));
+        assert!(!game.is_over());
+    }
+
+    #[test]
+    fn first_player_wins_on_one_board_and_loses_on_two() {
+        let mut solver = NotaktoSolver::new();
+        let one_board = solver.solve(&Notakto::new(1, Player::X));
+        assert!(one_board.mover_wins);
+
+        // The winning first move on a single board is the centre
+        let report = solver.choose_move(&Notakto::new(1, Player::X));
+        assert_eq!(
+            report.player_move,
+            NotaktoMove {
+                board: 0,
+                point: Point { x: 1, y: 1 }
+            }
+        );
+        assert!(report.score.unwrap() > WIN / 2);
+
+        // The hint's line is the whole game, ending with O killing the board
+        let mut game = Notakto::new(1, Player::X);
+        for player_move in &report.line {
+            GameVariant::play(&mut game, player_move).unwrap();
+        }
+        assert!(game.is_over());
+        assert_eq!(report.line.len() as i32, WIN - report.score.unwrap());
+
+        assert!(!solver.solve(&Notakto::new(2, Player::X)).mover_wins);
+    }
+
+    #[test]
+    fn first_player_wins_on_three_boards() {
+        assert!(
+            NotaktoSolver::new()
+                .solve(&Notakto::new(3, Player::X))
+                .mover_wins
+        );
+    }
+
+    #[test]
+    fn dead_and_rotated_boards_are_solved_alike() {
+        let mut solver = NotaktoSolver::new();
+        let mut game = Notakto::new(2, Player::X);
+        game.play(0, &Point { x: 0, y: 0 }).unwrap();
+        let mut rotated = Notakto::new(3, Player::X);
+        rotated.play(2, &Point { x: 2, y: 2 }).unwrap();
+        for y in 0..3 {
+            rotated.play(1, &Point { x: 0, y }).unwrap();
+        }
+        assert_eq!(solver.solve(&game), solver.solve(&rotated));
+    }
+
+    #[test]
+    fn boards_are_drawn_side_by_side() {
+        let mut game = Notakto::new(2, Player::X);
+        for y in 0..3 {
+            game.play(1, &Point { x: 1, y }).unwrap();
+        }
+        let drawing = game.to_string();
+        let lines: Vec<&str> = drawing.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "0               1 (dead)");
+        assert_eq!(lines[3], "   |   |         X | X | X");
+    }
+}
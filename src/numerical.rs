@@ -0,0 +1,350 @@
+use crate::board_info::{Player, Point};
+use crate::config::Config;
+use crate::game_variant::{self, GameVariant, Negamax, WIN};
+use crate::scoring::GameStatus;
+
+/// A move in numerical tic-tac-toe: a number written in a blank square
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumericalMove {
+    pub point: Point,
+    pub number: u32,
+}
+
+/// Written the way moves are entered, e.g. "1 1 5"
+impl std::fmt::Display for NumericalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.point.x, self.point.y, self.number)
+    }
+}
+
+/// A game of numerical tic-tac-toe. The numbers 1 to 9 are shared out: the first player writes the
+/// odd ones and the second player the even ones, each at most once. Whoever completes a line that
+/// adds up to 15 wins, whichever numbers are in it. On an n by n board the numbers go up to n^2
+/// and the lines have to add up to n(n^2 + 1)/2, the sum of the lines of a magic square.
+///
+/// The players are still `Player::X` and `Player::O`, whichever of them moves first, so
+/// `GameStatus::XWin` is a win for X whether X has the odd numbers or the even ones.
+#[derive(Debug, Clone)]
+pub struct Numerical {
+    size: usize,
+    cells: Vec<Vec<Option<u32>>>,
+    /// The player with the odd numbers, who moves first
+    odd: Player,
+    to_move: Player,
+    game_status: GameStatus,
+}
+
+impl Numerical {
+    /// How many plies the computer searches when `--depth` isn't given. Every blank square can
+    /// take any of the mover's numbers, so it can't search to the end.
+    pub const DEFAULT_DEPTH: u32 = 6;
+
+    /// An empty board of width `size`, with `first` to move and writing the odd numbers
+    pub fn new(size: usize, first: Player) -> Self {
+        Self {
+            size,
+            cells: vec![vec![None; size]; size],
+            odd: first,
+            to_move: first,
+            game_status: GameStatus::StillPlaying,
+        }
+    }
+
+    pub fn game_status(&self) -> GameStatus {
+        self.game_status
+    }
+
+    pub fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    /// The number written at `point`, if any
+    pub fn cell(&self, point: &Point) -> Option<u32> {
+        self.cells[point.x][point.y]
+    }
+
+    /// What a complete line has to add up to
+    pub fn target(&self) -> u32 {
+        let n = self.size as u32;
+        n * (n * n + 1) / 2
+    }
+
+    /// The numbers `player` hasn't written yet: odd ones for the first player and even ones for
+    /// the second
+    pub fn inventory(&self, player: &Player) -> Vec<u32> {
+        let first = if *player == self.odd { 1 } else { 2 };
+        let used: Vec<u32> = self.cells.iter().flatten().flatten().copied().collect();
+        (first..=(self.size * self.size) as u32)
+            .step_by(2)
+            .filter(|number| !used.contains(number))
+            .collect()
+    }
+
+    /// Every move that can be played next, in a stable order
+    pub fn legal_moves(&self) -> Vec<NumericalMove> {
+        if self.game_status != GameStatus::StillPlaying {
+            return Vec::new();
+        }
+        let inventory = self.inventory(&self.to_move);
+        self.squares()
+            .filter(|point| self.cell(point).is_none())
+            .flat_map(|point| {
+                inventory.iter().map(move |number| NumericalMove {
+                    point,
+                    number: *number,
+                })
+            })
+            .collect()
+    }
+
+    /// Write the number of `player_move` for the player to move
+    pub fn play(&mut self, player_move: &NumericalMove) -> Result<(), String> {
+        let NumericalMove { point, number } = *player_move;
+        if self.game_status != GameStatus::StillPlaying {
+            return Err("The game is over".to_string());
+        }
+        if point.x >= self.size || point.y >= self.size {
+            return Err(format!("Not a square of the board: {point}"));
+        }
+        if let Some(written) = self.cell(&point) {
+            return Err(format!("{written} has already been written at {point}"));
+        }
+        let inventory = self.inventory(&self.to_move);
+        if !inventory.contains(&number) {
+            let numbers: Vec<String> = inventory.iter().map(u32::to_string).collect();
+            return Err(format!(
+                "{number} is not one of your numbers. You have {}",
+                numbers.join(" ")
+            ));
+        }
+
+        self.cells[point.x][point.y] = Some(number);
+        let mover = self.to_move;
+        self.to_move = mover.other();
+        self.game_status = if self.completes_line(&point) {
+            match mover {
+                Player::X => GameStatus::XWin,
+                Player::O => GameStatus::OWin,
+            }
+        } else if self.legal_moves().is_empty() {
+            GameStatus::Draw
+        } else {
+            GameStatus::StillPlaying
+        };
+        Ok(())
+    }
+
+    // Lines are judged by what they add up to rather than by whether their squares match, so a
+    // line only counts once every square of it has a number
+    fn completes_line(&self, point: &Point) -> bool {
+        let n = self.size;
+        let mut lines: Vec<Vec<Point>> = vec![
+            (0..n).map(|y| Point { x: point.x, y }).collect(),
+            (0..n).map(|x| Point { x, y: point.y }).collect(),
+        ];
+        if point.x == point.y {
+            lines.push((0..n).map(|i| Point { x: i, y: i }).collect());
+        }
+        if point.x + point.y == n - 1 {
+            lines.push((0..n).map(|i| Point { x: i, y: n - 1 - i }).collect());
+        }
+        lines.iter().any(|line| {
+            line.iter()
+                .map(|point| self.cell(point))
+                .sum::<Option<u32>>()
+                == Some(self.target())
+        })
+    }
+
+    fn squares(&self) -> impl Iterator<Item = Point> {
+        let n = self.size;
+        (0..n).flat_map(move |x| (0..n).map(move |y| Point { x, y }))
+    }
+}
+
+/// The classic board with the numbers in the squares, widened to fit the largest number
+impl std::fmt::Display for Numerical {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = (self.size * self.size).to_string().len();
+        let rows: Vec<String> = self
+            .cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        Some(number) => format!(" {number:>width$} "),
+                        None => format!(" {:width$} ", ""),
+                    })
+                    .collect::<Vec<String>>()
+                    .join("|")
+            })
+            .collect();
+        let separator = format!("\n{}\n", "-".repeat(self.size * (width + 3)));
+        write!(f, "{}", rows.join(&separator))
+    }
+}
+
+impl GameVariant for Numerical {
+    type Move = NumericalMove;
+
+    fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    fn legal_moves(&self) -> Vec<NumericalMove> {
+        Numerical::legal_moves(self)
+    }
+
+    // The moves that complete a line first
+    fn search_moves(&self) -> Vec<NumericalMove> {
+        let mut moves = self.legal_moves();
+        moves.sort_by_key(|player_move| {
+            let mut new_game = self.clone();
+            let _ = Numerical::play(&mut new_game, player_move);
+            !matches!(new_game.game_status, GameStatus::XWin | GameStatus::OWin)
+        });
+        moves
+    }
+
+    fn play(&mut self, player_move: &NumericalMove) -> Result<(), String> {
+        Numerical::play(self, player_move)
+    }
+
+    fn is_over(&self) -> bool {
+        self.game_status != GameStatus::StillPlaying
+    }
+
+    // Whatever can't be seen to the end is scored as even
+    fn score(&self) -> i32 {
+        match (self.game_status, self.to_move) {
+            (GameStatus::XWin, Player::X) | (GameStatus::OWin, Player::O) => WIN,
+            (GameStatus::XWin, Player::O) | (GameStatus::OWin, Player::X) => -WIN,
+            (GameStatus::Draw | GameStatus::StillPlaying, _) => 0,
+        }
+    }
+
+    // A move is "x y number"
+    fn parse_move(&self, input: &str) -> Result<NumericalMove, String> {
+        let numbers = input
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|e| e.to_string())?;
+        match numbers[..] {
+            [x, y, number] => Ok(NumericalMove {
+                point: Point { x, y },
+                number: number as u32,
+            }),
+            _ => Err(format!(
+                "Incorrect number of arguments. Expected 3 (x y number). Got {}",
+                numbers.len()
+            )),
+        }
+    }
+
+    fn note(&self) -> Option<String> {
+        let numbers: Vec<String> = self
+            .inventory(&self.to_move)
+            .iter()
+            .map(u32::to_string)
+            .collect();
+        Some(format!("Numbers left to write: {}", numbers.join(" ")))
+    }
+
+    fn status(&self) -> String {
+        format!("{:?}", self.game_status)
+    }
+}
+
+#[rustfmt::skip]
+impl Numerical {
+    const INTRO_MESSAGE: &'static str =
+        "\nWelcome to Numerical Tic-Tac-Toe!\
+         \n---------------------------------\n\
+         \n\
+         The first player writes the odd numbers and the second player the even ones,\n\
+         each at most once. Whoever completes a line that adds up to the target wins,\n\
+         whichever numbers are in it.\n\
+         \n\
+         Play by entering the coordinates of a square followed by the number you want\n\
+         to write there (e.g., \"1 1 5\"). Enter \"hint\" to see the computer's suggestion.\n\
+         \n\
+         ##################################################\n";
+}
+
+impl Numerical {
+    /// Play numerical tic-tac-toe against the computer. Whoever moves first has the odd numbers.
+    pub fn run(config: &Config) {
+        let size = config.board_size;
+        let target = Numerical::new(size, Player::X).target();
+        let intro = format!("{}\nLines have to add up to {target}", Self::INTRO_MESSAGE);
+        let mut engine = Negamax::new(config.depth.unwrap_or(Self::DEFAULT_DEPTH));
+        let new_game = |first, _| Numerical::new(size, first);
+        game_variant::run(config, &intro, new_game, &mut engine);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_variant::VariantEngine;
+
+    fn play_all(game: &mut Numerical, moves: &[(usize, usize, u32)]) {
+        for (x, y, number) in moves {
+            game.play(&NumericalMove {
+                point: Point { x: *x, y: *y },
+                number: *number,
+            })
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn numbers_are_shared_out_by_parity_and_used_once() {
+        // Whoever moves first has the odd numbers
+        let game = Numerical::new(3, Player::O);
+        assert_eq!(game.inventory(&Player::O), vec![1, 3, 5, 7, 9]);
+
+        let mut game = Numerical::new(3, Player::X);
+        play_all(&mut game, &[(0, 0, 9), (1, 1, 2)]);
+        assert_eq!(game.inventory(&Player::X), vec![1, 3, 5, 7]);
+        assert_eq!(game.inventory(&Player::O), vec![4, 6, 8]);
+        for number in [9, 4] {
+            let player_move = NumericalMove {
+                point: Point { x: 2, y: 2 },
+                number,
+            };
+            assert!(game.play(&player_move).is_err());
+        }
+    }
+
+    #[test]
+    fn a_full_line_adding_up_to_15_wins_for_whoever_completed_it() {
+        let mut game = Numerical::new(3, Player::X);
+        assert_eq!(game.target(), 15);
+        // The top row is full, but only adds up to 14
+        play_all(&mut game, &[(0, 0, 9), (0, 1, 2), (0, 2, 3)]);
+        assert_eq!(game.game_status(), GameStatus::StillPlaying);
+
+        // O completes the diagonal, even though the 9 in it is X's
+        let mut game = Numerical::new(3, Player::X);
+        play_all(&mut game, &[(0, 0, 9), (1, 1, 2), (0, 1, 1), (2, 2, 4)]);
+        assert_eq!(game.game_status(), GameStatus::OWin);
+        assert!(game.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn engine_completes_a_line() {
+        let mut game = Numerical::new(3, Player::X);
+        play_all(&mut game, &[(0, 0, 9), (1, 1, 2), (0, 1, 1)]);
+        let report = Negamax::new(2).choose_move(&game);
+        assert_eq!(
+            report.player_move,
+            NumericalMove {
+                point: Point { x: 2, y: 2 },
+                number: 4
+            }
+        );
+        assert!(report.score.unwrap() > 0);
+    }
+}
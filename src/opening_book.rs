@@ -0,0 +1,422 @@
+use crate::board_info::{Player, Point, Rules};
+use crate::engine::ComputerPlayer;
+use crate::game_variant::{GameVariant, Placement, Position, Report, VariantEngine};
+use crate::mcts::SplitMix64;
+use crate::scoring::GameStatus;
+use crate::symmetry::canonical_position;
+use crate::tic_tac_toe_board::Board;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+/// A recommended move in the opening book. Moves with a larger weight are played more often.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookMove {
+    pub player_move: Point,
+    pub weight: u32,
+}
+
+/// Recommended moves for the first few positions of a game, worked out ahead of time so the
+/// computer doesn't have to search the positions that take it the longest.
+///
+/// Positions are stored by their canonical form (see `symmetry::canonical_position()`), so one
+/// entry covers every rotation and reflection of a position. The moves of an entry are stored
+/// relative to the canonical position and transformed back when they are looked up.
+///
+/// A book is only good for the board size and rules it was built with, so books are saved as
+/// text starting with a line that records them, followed by one position per line:
+///
+/// ```text
+/// rules 4 misere gravity
+/// # position player_to_move move:weight ...
+/// BBBB/BBBB/BBBB/BBBB X 0,0:3 1,1:1
+/// ```
+///
+/// where the rules line gives the width of the board and the names of the rules in play (any of
+/// `misere`, `wild`, `gravity` and `torus`), the position is written as `Board::position()` writes
+/// it and each move is the `x,y` coordinates of a square followed by its weight. Blank lines and
+/// lines starting with `#` are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpeningBook {
+    size: usize,
+    rules: Rules,
+    entries: HashMap<(String, Player), Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    /// An empty book for games on a `size` by `size` board played with `rules`
+    pub fn new(size: usize, rules: Rules) -> Self {
+        Self {
+            size,
+            rules,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// The number of positions in the book
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add `weight` to the recommendation of `player_move` for `player` in the position `board`
+    pub fn add(&mut self, board: &Board, player: &Player, player_move: &Point, weight: u32) {
+        let (position, transform) = canonical_position(board);
+        let player_move = transform.apply(player_move, board.size());
+        let moves = self.entries.entry((position, *player)).or_default();
+        match moves
+            .iter_mut()
+            .find(|book_move| book_move.player_move == player_move)
+        {
+            Some(book_move) => book_move.weight += weight,
+            None => moves.push(BookMove {
+                player_move,
+                weight,
+            }),
+        }
+    }
+
+    /// The recommended moves for `player` in the position `board`, in the board's own orientation.
+    /// Empty when the position isn't in the book.
+    pub fn moves(&self, board: &Board, player: &Player) -> Vec<BookMove> {
+        let (position, transform) = canonical_position(board);
+        self.entries
+            .get(&(position, *player))
+            .into_iter()
+            .flatten()
+            .map(|book_move| BookMove {
+                player_move: transform.unapply(&book_move.player_move, board.size()),
+                weight: book_move.weight,
+            })
+            .filter(|book_move| board.legal_moves().contains(&book_move.player_move))
+            .collect()
+    }
+
+    /// Fill a book for games starting from `empty_board` by asking `engine` for its move in every
+    /// position of the first `plies` plies, whichever player moves first. Each position is searched
+    /// `samples` times and every answer adds one to the weight of the move chosen, so engines
+    /// that don't always pick the same move (like `Mcts`) spread the weight between their
+    /// favourites.
+    pub fn build(
+        empty_board: &Board,
+        plies: usize,
+        engine: &mut dyn ComputerPlayer,
+        samples: u32,
+    ) -> Self {
+        let mut book = Self::new(empty_board.size(), empty_board.rules());
+        let mut seen = HashSet::new();
+        let mut positions = vec![
+            (empty_board.clone(), Player::X),
+            (empty_board.clone(), Player::O),
+        ];
+        while let Some((board, player)) = positions.pop() {
+            if board.squares_filled() >= plies
+                || board.game_status() != GameStatus::StillPlaying
+                || !seen.insert((canonical_position(&board).0, player))
+            {
+                continue;
+            }
+
+            for _ in 0..samples {
+                let report = engine.choose_move(&board, &player);
+                book.add(&board, &player, &report.player_move, 1);
+            }
+            for point in board.legal_moves() {
+                for piece in board.playable_pieces(&player) {
+                    let mut new_board = board.clone();
+                    let _ = new_board.place(&point, piece, &player);
+                    positions.push((new_board, player.other()));
+                }
+            }
+        }
+        book
+    }
+
+    /// Read a book in its file format, for games on a `size` by `size` board played with `rules`.
+    /// Books built for another board size or other rules are refused.
+    pub fn parse(text: &str, size: usize, rules: Rules) -> Result<Self, String> {
+        let mut book = Self::new(size, rules);
+        let mut rules_checked = false;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            // Positions can start with a blocked square ('#'), so comments start with "# "
+            if line.is_empty() || line == "#" || line.starts_with("# ") {
+                continue;
+            }
+            let error = |e: String| format!("Opening book line {}: {e}", number + 1);
+
+            if !rules_checked {
+                let expected = rules_line(size, rules);
+                if line.split_whitespace().ne(expected.split_whitespace()) {
+                    return Err(error(format!(
+                        "the book is for {line:?}, but the game is played with {expected:?}"
+                    )));
+                }
+                rules_checked = true;
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            let board =
+                Board::from_position(fields.next().unwrap_or_default(), rules).map_err(error)?;
+            let player = match fields.next() {
+                Some("X") => Player::X,
+                Some("O") => Player::O,
+                x => return Err(error(format!("expected \"X\" or \"O\" to move. Got {x:?}"))),
+            };
+            for field in fields {
+                let book_move = parse_book_move(field)
+                    .filter(|book_move| board.legal_moves().contains(&book_move.player_move))
+                    .ok_or_else(|| error(format!("not a valid move and weight: {field:?}")))?;
+                book.add(&board, &player, &book_move.player_move, book_move.weight);
+            }
+        }
+        Ok(book)
+    }
+
+    pub fn load(path: &std::path::Path, size: usize, rules: Rules) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(&text, size, rules)
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+// The first line of a book file, e.g. "rules 4 misere gravity"
+fn rules_line(size: usize, rules: Rules) -> String {
+    let mut line = format!("rules {size}");
+    for (name, in_play) in [
+        ("misere", rules.misere),
+        ("wild", rules.wild),
+        ("gravity", rules.gravity),
+        ("torus", rules.torus),
+    ] {
+        if in_play {
+            line += " ";
+            line += name;
+        }
+    }
+    line
+}
+
+// A move written as "x,y:weight"
+fn parse_book_move(field: &str) -> Option<BookMove> {
+    let (point, weight) = field.split_once(':')?;
+    Some(BookMove {
+        player_move: point.parse().ok()?,
+        weight: weight.parse().ok()?,
+    })
+}
+
+/// Writes the book in its file format, in a stable order with the heaviest moves first
+impl std::fmt::Display for OpeningBook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", rules_line(self.size, self.rules))?;
+        writeln!(f, "# position player_to_move move:weight ...")?;
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by_key(|((position, player), _)| {
+            (
+                Reverse(position.matches('B').count()),
+                position.clone(),
+                *player == Player::O,
+            )
+        });
+        for ((position, player), moves) in entries {
+            let mut moves = moves.clone();
+            moves.sort_by_key(|book_move| (Reverse(book_move.weight), book_move.player_move));
+            write!(f, "{position} {player}")?;
+            for book_move in moves {
+                let Point { x, y } = book_move.player_move;
+                write!(f, " {x},{y}:{}", book_move.weight)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A computer opponent that plays from an `OpeningBook` while it can, picking between the
+/// recommended moves at random in proportion to their weights, and asks another computer
+/// opponent once the game leaves the book.
+pub struct BookPlayer {
+    book: OpeningBook,
+    fallback: Box<dyn VariantEngine<Position>>,
+    rng: SplitMix64,
+}
+
+impl BookPlayer {
+    pub fn new(
+        book: OpeningBook,
+        fallback: Box<dyn VariantEngine<Position>>,
+        seed: Option<u64>,
+    ) -> Self {
+        Self {
+            book,
+            fallback,
+            rng: seed.map_or_else(SplitMix64::from_time, SplitMix64::new),
+        }
+    }
+}
+
+impl VariantEngine<Position> for BookPlayer {
+    fn choose_move(&mut self, game: &Position) -> Report<Placement> {
+        let start = Instant::now();
+        let player = game.to_move();
+        let piece = player.square_type();
+        // Book moves the position rules out, like the corner handicap can, aren't played
+        let legal_moves = game.legal_moves();
+        let moves: Vec<BookMove> = self
+            .book
+            .moves(game.board(), &player)
+            .into_iter()
+            .filter(|book_move| {
+                legal_moves.contains(&Placement {
+                    point: book_move.player_move,
+                    piece,
+                })
+            })
+            .collect();
+        let total_weight: u32 = moves.iter().map(|book_move| book_move.weight).sum();
+        if total_weight == 0 {
+            return self.fallback.choose_move(game);
+        }
+
+        let mut pick = self.rng.below(total_weight as usize) as u32;
+        let point = moves
+            .iter()
+            .find(|book_move| {
+                if pick < book_move.weight {
+                    true
+                } else {
+                    pick -= book_move.weight;
+                    false
+                }
+            })
+            .unwrap()
+            .player_move;
+
+        let player_move = Placement { point, piece };
+        Report {
+            line: vec![player_move],
+            player_move,
+            score: None,
+            nodes: 0,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Handicap;
+    use crate::engine::AlphaBeta;
+    use crate::game_variant::Negamax;
+
+    #[test]
+    fn book_moves_follow_the_position_through_symmetries() {
+        let mut book = OpeningBook::new(3, Rules::default());
+        let board = Board::from_string("X B B | B B B | B B B");
+        book.add(&board, &Player::O, &Point { x: 1, y: 1 }, 2);
+        book.add(&board, &Player::O, &Point { x: 0, y: 1 }, 1);
+        assert_eq!(book.len(), 1);
+
+        // The same position rotated a quarter turn: the edge reply rotates with it
+        let rotated = Board::from_string("B B X | B B B | B B B");
+        let mut moves = book.moves(&rotated, &Player::O);
+        moves.sort_by_key(|book_move| book_move.player_move);
+        assert_eq!(
+            moves,
+            vec![
+                BookMove {
+                    player_move: Point { x: 1, y: 1 },
+                    weight: 2
+                },
+                BookMove {
+                    player_move: Point { x: 1, y: 2 },
+                    weight: 1
+                },
+            ]
+        );
+        assert!(book.moves(&rotated, &Player::X).is_empty());
+    }
+
+    #[test]
+    fn saved_book_parses_back() {
+        let book = OpeningBook::build(
+            &Board::initialize_blank_board(3),
+            2,
+            &mut AlphaBeta { threads: 1 },
+            1,
+        );
+        // The empty board and its three distinct replies, for both players moving first
+        assert_eq!(book.len(), 8);
+        assert_eq!(
+            OpeningBook::parse(&book.to_string(), 3, Rules::default()),
+            Ok(book)
+        );
+    }
+
+    #[test]
+    fn malformed_books_are_rejected() {
+        let parse = |text: &str| OpeningBook::parse(text, 3, Rules::default());
+        assert!(parse("rules 3\nBBB/BBB/BBB X 1,1:1\n\n# comment").is_ok());
+        assert!(parse("BBB/BBB/BBB X 1,1:1").is_err());
+        assert!(parse("rules 3\nBBB/BBB/BBB Y 1,1:1").is_err());
+        assert!(parse("rules 3\nBBB/BBB X 1,1:1").is_err());
+        assert!(parse("rules 3\nBBB/BBB/BBB X 1,1").is_err());
+        assert!(parse("rules 3\nXBB/BBB/BBB O 0,0:1").is_err());
+    }
+
+    #[test]
+    fn books_for_other_rules_are_refused() {
+        let misere = Rules {
+            misere: true,
+            ..Rules::default()
+        };
+        let book = OpeningBook::build(
+            &Board::with_rules(3, misere),
+            1,
+            &mut AlphaBeta { threads: 1 },
+            1,
+        );
+        let text = book.to_string();
+        assert!(text.starts_with("rules 3 misere\n"));
+        assert_eq!(OpeningBook::parse(&text, 3, misere), Ok(book));
+        assert!(OpeningBook::parse(&text, 3, Rules::default()).is_err());
+        assert!(OpeningBook::parse(&text, 4, misere).is_err());
+    }
+
+    #[test]
+    fn book_player_uses_the_book_then_falls_back() {
+        let mut book = OpeningBook::new(3, Rules::default());
+        let empty_board = Board::initialize_blank_board(3);
+        book.add(&empty_board, &Player::X, &Point { x: 0, y: 0 }, 1);
+        let mut player = BookPlayer::new(book, Box::new(Negamax::new(9)), Some(1));
+
+        let report = player.choose_move(&Position::new(empty_board.clone(), Player::X));
+        assert_eq!(report.player_move.point, Point { x: 0, y: 0 });
+        assert_eq!(report.score, None);
+
+        // Out of the book the fallback searches the position, and blocks O's row
+        let board = Board::from_string("O O B | X B B | X B B");
+        let report = player.choose_move(&Position::new(board, Player::X));
+        assert_eq!(report.player_move.point, Point { x: 0, y: 2 });
+        assert!(report.score.is_some());
+
+        // Under the corner handicap, the book's centre reply gives way to the search
+        let board = Board::from_string("X B B | B B B | B B B");
+        let mut book = OpeningBook::new(3, Rules::default());
+        book.add(&board, &Player::O, &Point { x: 1, y: 1 }, 1);
+        let mut player = BookPlayer::new(book, Box::new(Negamax::new(9)), Some(1));
+        let game = Position::with_handicaps(board, Player::O, Player::X, &[Handicap::Corner]);
+        let report = player.choose_move(&game);
+        assert!(report.score.is_some());
+        assert_ne!(report.player_move.point, Point { x: 1, y: 1 });
+    }
+}
@@ -0,0 +1,393 @@
+use crate::board_info::{Player, Point, SquareType};
+use crate::config::{Config, MoveFirst};
+use crate::game_variant::{self, GameVariant, Negamax, WIN};
+use crate::tic_tac_toe_board::Board;
+
+/// The two sides of Order and Chaos. Both place either piece, so the sides are told apart by what
+/// they are trying to do rather than by their pieces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Wins by making five of the same piece in a row
+    Order,
+    /// Wins by filling the board without five of the same piece in a row
+    Chaos,
+}
+
+impl Role {
+    pub fn other(&self) -> Role {
+        match self {
+            Role::Order => Role::Chaos,
+            Role::Chaos => Role::Order,
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::Order => write!(f, "Order"),
+            Role::Chaos => write!(f, "Chaos"),
+        }
+    }
+}
+
+/// A move in Order and Chaos: either piece at a blank square
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderChaosMove {
+    pub point: Point,
+    pub piece: SquareType,
+}
+
+/// Written the way moves are entered, e.g. "2 3 O"
+impl std::fmt::Display for OrderChaosMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.point.x, self.point.y, self.piece)
+    }
+}
+
+/// A game of Order and Chaos. Order moves first, and each turn either side places an X or an O.
+/// Order wins as soon as there are five of the same piece in a row, column, or diagonal, and
+/// Chaos wins if the board fills up first.
+///
+/// Since neither side owns a piece, the `Player` who moves first plays Order and the other one
+/// plays Chaos.
+#[derive(Debug, Clone)]
+pub struct OrderAndChaos {
+    /// Stores and draws the pieces. Its own status looks for lines across the whole board rather
+    /// than five in a row, so it isn't used.
+    board: Board,
+    /// Every run of `LINE` squares in a row, column, or diagonal
+    lines: Vec<Vec<Point>>,
+    /// The player who plays Order
+    order: Player,
+    to_move: Player,
+    winner: Option<Role>,
+}
+
+impl OrderAndChaos {
+    /// The classic board is 6x6
+    pub const SIZE: usize = 6;
+    /// How many of the same piece in a row Order needs
+    pub const LINE: usize = 5;
+    /// How many plies the computer searches when `--depth` isn't given. With two pieces to
+    /// choose from on every square there are far too many moves to search to the end.
+    pub const DEFAULT_DEPTH: u32 = 2;
+
+    /// An empty board of width `size`, which must be at least `LINE`, with `first` playing Order
+    pub fn new(size: usize, first: Player) -> Self {
+        let mut lines = Vec::new();
+        let directions: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+        for x in 0..size {
+            for y in 0..size {
+                for (dx, dy) in directions {
+                    let line: Option<Vec<Point>> = (0..Self::LINE as isize)
+                        .map(|i| {
+                            let x = x.checked_add_signed(dx * i).filter(|x| *x < size)?;
+                            let y = y.checked_add_signed(dy * i).filter(|y| *y < size)?;
+                            Some(Point { x, y })
+                        })
+                        .collect();
+                    lines.extend(line);
+                }
+            }
+        }
+        Self {
+            board: Board::initialize_blank_board(size),
+            lines,
+            order: first,
+            to_move: first,
+            winner: None,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The side the player to move is playing
+    pub fn role_to_move(&self) -> Role {
+        if self.to_move == self.order {
+            Role::Order
+        } else {
+            Role::Chaos
+        }
+    }
+
+    /// Who has won. `None` while the game is still being played.
+    pub fn winner(&self) -> Option<Role> {
+        self.winner
+    }
+
+    /// Every move that can be played next, in a stable order
+    pub fn legal_moves(&self) -> Vec<OrderChaosMove> {
+        if self.winner.is_some() {
+            return Vec::new();
+        }
+        self.board
+            .blank_squares()
+            .into_iter()
+            .flat_map(|point| {
+                [SquareType::X, SquareType::O].map(|piece| OrderChaosMove { point, piece })
+            })
+            .collect()
+    }
+
+    /// Place the piece of `player_move` for the player to move. Either side may place either
+    /// piece.
+    pub fn play(&mut self, player_move: &OrderChaosMove) -> Result<(), String> {
+        if let Some(winner) = self.winner {
+            return Err(format!("The game is over: {winner} has won"));
+        }
+        let OrderChaosMove { point, piece } = *player_move;
+        self.board.insert(&point, piece)?;
+        let five = self
+            .lines
+            .iter()
+            .filter(|line| line.contains(&point))
+            .any(|line| line.iter().all(|square| self.board.square(square) == piece));
+        if five {
+            self.winner = Some(Role::Order);
+        } else if self.board.blank_squares_remaining() == 0 {
+            self.winner = Some(Role::Chaos);
+        }
+        self.to_move = self.to_move.other();
+        Ok(())
+    }
+
+    /// How good the position looks for Order (positive) or Chaos (negative), for when searching
+    /// to the end of the game isn't feasible. A run of five squares only helps Order while it
+    /// holds one kind of piece, and the more it holds the more it is worth. Once no run can be
+    /// completed Chaos is sure to win.
+    pub fn evaluate(&self) -> i32 {
+        match self.winner {
+            Some(Role::Order) => return WIN,
+            Some(Role::Chaos) => return -WIN,
+            None => (),
+        }
+
+        let mut live_lines = 0;
+        let mut score = 0;
+        for line in &self.lines {
+            let count = |piece| {
+                line.iter()
+                    .filter(|square| self.board.square(square) == piece)
+                    .count()
+            };
+            match (count(SquareType::X), count(SquareType::O)) {
+                (pieces, 0) | (0, pieces) => {
+                    live_lines += 1;
+                    score += [1, 2, 8, 32, 128][pieces];
+                }
+                _ => (),
+            }
+        }
+        if live_lines == 0 {
+            return -WIN;
+        }
+        score
+    }
+}
+
+impl std::fmt::Display for OrderAndChaos {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.board)
+    }
+}
+
+impl GameVariant for OrderAndChaos {
+    type Move = OrderChaosMove;
+
+    fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    fn legal_moves(&self) -> Vec<OrderChaosMove> {
+        OrderAndChaos::legal_moves(self)
+    }
+
+    // The moves that look best for the side to move by `evaluate()` first
+    fn search_moves(&self) -> Vec<OrderChaosMove> {
+        let mut moves: Vec<(OrderChaosMove, i32)> = self
+            .legal_moves()
+            .into_iter()
+            .map(|player_move| {
+                let mut new_game = self.clone();
+                let _ = OrderAndChaos::play(&mut new_game, &player_move);
+                (player_move, new_game.evaluate())
+            })
+            .collect();
+        match self.role_to_move() {
+            Role::Order => moves.sort_by_key(|(_, score)| std::cmp::Reverse(*score)),
+            Role::Chaos => moves.sort_by_key(|(_, score)| *score),
+        }
+        moves
+            .into_iter()
+            .map(|(player_move, _)| player_move)
+            .collect()
+    }
+
+    fn play(&mut self, player_move: &OrderChaosMove) -> Result<(), String> {
+        OrderAndChaos::play(self, player_move)
+    }
+
+    fn is_over(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    fn score(&self) -> i32 {
+        match self.role_to_move() {
+            Role::Order => self.evaluate(),
+            Role::Chaos => -self.evaluate(),
+        }
+    }
+
+    // A move is "x y piece"
+    fn parse_move(&self, input: &str) -> Result<OrderChaosMove, String> {
+        let fields: Vec<&str> = input.split_whitespace().collect();
+        let [x, y, piece] = fields[..] else {
+            return Err(format!(
+                "Incorrect number of arguments. Expected 3 (x y piece). Got {}",
+                fields.len()
+            ));
+        };
+        let piece = match piece {
+            "x" | "X" => SquareType::X,
+            "o" | "O" => SquareType::O,
+            piece => return Err(format!("Expected the piece \"X\" or \"O\". Got {piece:?}")),
+        };
+        Ok(OrderChaosMove {
+            point: Point {
+                x: x.parse().map_err(|e| format!("{e}"))?,
+                y: y.parse().map_err(|e| format!("{e}"))?,
+            },
+            piece,
+        })
+    }
+
+    fn note(&self) -> Option<String> {
+        Some(format!("{} to move", self.role_to_move()))
+    }
+
+    fn status(&self) -> String {
+        match self.winner {
+            Some(winner) => format!("{winner} wins"),
+            None => "StillPlaying".to_string(),
+        }
+    }
+}
+
+#[rustfmt::skip]
+impl OrderAndChaos {
+    const INTRO_MESSAGE: &'static str =
+        "\nWelcome to Order and Chaos!\
+         \n---------------------------\n\
+         \n\
+         Each turn either side places an X or an O. Order wins with five of the same\n\
+         piece in a row, column, or diagonal, and Chaos wins if the board fills up\n\
+         without one. Order moves first.\n\
+         \n\
+         Play by entering the coordinates of a square followed by the piece you want\n\
+         to place there (e.g., \"2 3 O\"). Enter \"hint\" to see the computer's suggestion.\n\
+         \n\
+         ##################################################\n";
+}
+
+impl OrderAndChaos {
+    /// Play Order and Chaos against the computer. Order moves first, so moving first means playing
+    /// Order and moving second means playing Chaos.
+    pub fn run(config: &Config) {
+        let user_role = match config.first_or_second {
+            MoveFirst(true) => Role::Order,
+            MoveFirst(false) => Role::Chaos,
+        };
+        let intro = format!("{}\nYou are playing {user_role}", Self::INTRO_MESSAGE);
+        let mut engine = Negamax::new(config.depth.unwrap_or(Self::DEFAULT_DEPTH));
+        let size = config.board_size;
+        let new_game = |first, _| OrderAndChaos::new(size, first);
+        game_variant::run(config, &intro, new_game, &mut engine);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_variant::VariantEngine;
+
+    fn play_all(game: &mut OrderAndChaos, moves: &[(usize, usize, SquareType)]) {
+        for (x, y, piece) in moves {
+            game.play(&OrderChaosMove {
+                point: Point { x: *x, y: *y },
+                piece: *piece,
+            })
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn five_of_either_piece_wins_for_order() {
+        use SquareType::{O, X};
+        let mut game = OrderAndChaos::new(OrderAndChaos::SIZE, Player::X);
+        // Two runs of five along each row and column, and eight along the diagonals
+        assert_eq!(game.lines.len(), 32);
+
+        play_all(
+            &mut game,
+            &[(0, 0, X), (0, 1, X), (0, 2, O), (0, 3, X), (0, 4, X)],
+        );
+        assert_eq!(game.winner(), None);
+        play_all(&mut game, &[(1, 1, O), (2, 2, O), (3, 3, O), (4, 4, O)]);
+        assert_eq!(game.winner(), None);
+        play_all(&mut game, &[(5, 5, O)]);
+        assert_eq!(game.winner(), Some(Role::Order));
+        assert!(game.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn chaos_wins_by_filling_the_board() {
+        // A checkerboard of 2x1 blocks never has more than two of a piece in a line
+        let mut game = OrderAndChaos::new(OrderAndChaos::SIZE, Player::X);
+        for x in 0..OrderAndChaos::SIZE {
+            for y in 0..OrderAndChaos::SIZE {
+                let piece = if (x / 2 + y) % 2 == 0 {
+                    SquareType::X
+                } else {
+                    SquareType::O
+                };
+                assert_eq!(game.winner(), None);
+                play_all(&mut game, &[(x, y, piece)]);
+            }
+        }
+        assert_eq!(game.winner(), Some(Role::Chaos));
+    }
+
+    #[test]
+    fn engine_plays_either_side() {
+        use SquareType::X;
+        let mut game = OrderAndChaos::new(OrderAndChaos::SIZE, Player::X);
+        play_all(&mut game, &[(0, 0, X), (0, 1, X), (0, 2, X), (0, 3, X)]);
+
+        // Order completes the run with an X
+        let mut engine = Negamax::new(OrderAndChaos::DEFAULT_DEPTH);
+        assert_eq!(game.role_to_move(), Role::Order);
+        let order = engine.choose_move(&game).player_move;
+        assert_eq!(
+            order,
+            OrderChaosMove {
+                point: Point { x: 0, y: 4 },
+                piece: X
+            }
+        );
+
+        // Once Order plays elsewhere, Chaos has to spoil the run with an O
+        play_all(&mut game, &[(5, 5, X)]);
+        assert_eq!(game.role_to_move(), Role::Chaos);
+        let chaos = engine.choose_move(&game).player_move;
+        assert_eq!(
+            chaos,
+            OrderChaosMove {
+                point: Point { x: 0, y: 4 },
+                piece: SquareType::O
+            }
+        );
+    }
+}
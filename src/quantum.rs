@@ -0,0 +1,521 @@
+use crate::board_info::{Player, Point};
+use crate::config::Config;
+use crate::game_variant::{self, GameVariant, Negamax, WIN};
+use std::collections::VecDeque;
+
+/// A mark of quantum tic-tac-toe: whose it is and the number of the move that made it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mark {
+    pub player: Player,
+    pub subscript: u32,
+}
+
+/// Written as the player and the subscript, e.g. "X3"
+impl std::fmt::Display for Mark {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.player, self.subscript)
+    }
+}
+
+/// A move in quantum tic-tac-toe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantumMove {
+    /// Spooky marks in two squares, entangling them
+    Entangle(Point, Point),
+    /// A classical mark in the only square left that isn't classical
+    Place(Point),
+    /// The square the mark that closed a cycle of entanglement collapses into. The player who
+    /// didn't close the cycle chooses.
+    Collapse(Point),
+}
+
+/// Written the way moves are entered, e.g. "0 0 1 1" or "2 2"
+impl std::fmt::Display for QuantumMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Entangle(a, b) => write!(f, "{} {} {} {}", a.x, a.y, b.x, b.y),
+            Self::Place(point) | Self::Collapse(point) => write!(f, "{} {}", point.x, point.y),
+        }
+    }
+}
+
+/// How a game of quantum tic-tac-toe ended. A collapse can complete lines for both players at once,
+/// so unlike `GameStatus` a result can have two winners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantumResult {
+    Win(Player),
+    /// Both players completed a line in the same collapse. The player whose line was finished
+    /// sooner (the lower of the highest subscripts in each line) scores a point and the other half
+    /// a point.
+    SplitWin {
+        first: Player,
+    },
+    Draw,
+}
+
+impl QuantumResult {
+    /// The points `player` scores, in half points
+    pub fn half_points(&self, player: &Player) -> u32 {
+        match self {
+            Self::Win(winner) if winner == player => 2,
+            Self::Win(_) | Self::Draw => 0,
+            Self::SplitWin { first } if first == player => 2,
+            Self::SplitWin { .. } => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for QuantumResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Win(winner) => write!(f, "{winner} wins"),
+            Self::SplitWin { first } => write!(
+                f,
+                "Both players completed a line. {first} finished first and scores 1, and {} scores 1/2",
+                first.other()
+            ),
+            Self::Draw => write!(f, "Draw"),
+        }
+    }
+}
+
+/// A game of quantum tic-tac-toe. Each move puts two "spooky" marks in different squares, and the
+/// mark is really in only one of them, which isn't known yet. Once the spooky marks make a cycle
+/// of entangled squares, the player who didn't make it chooses which of its two squares the last
+/// mark goes in. That collapses the whole cycle, and everything entangled with it, into classical
+/// marks, since every other spooky mark in a square that gets a classical mark has to be in its
+/// other square. Lines only count once all three of their marks are classical.
+#[derive(Debug, Clone)]
+pub struct Quantum {
+    classical: Vec<Vec<Option<Mark>>>,
+    /// The spooky marks that haven't collapsed yet, with the two squares each one is in
+    spooky: Vec<(Mark, Point, Point)>,
+    to_move: Player,
+    /// The subscript of the next move
+    turn: u32,
+    /// The mark that closed a cycle, waiting for the player to move to choose where it collapses
+    pending_collapse: Option<(Mark, Point, Point)>,
+    result: Option<QuantumResult>,
+}
+
+impl Quantum {
+    pub const SIZE: usize = 3;
+    /// How many moves the engine searches when `--depth` isn't given
+    pub const DEFAULT_DEPTH: u32 = 3;
+
+    /// The empty board with `first` to move
+    pub fn new(first: Player) -> Self {
+        Self {
+            classical: vec![vec![None; Self::SIZE]; Self::SIZE],
+            spooky: Vec::new(),
+            to_move: first,
+            turn: 1,
+            pending_collapse: None,
+            result: None,
+        }
+    }
+
+    pub fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    /// How the game ended. `None` while it is still being played.
+    pub fn result(&self) -> Option<QuantumResult> {
+        self.result
+    }
+
+    /// The classical mark at `point`, if it has collapsed into one
+    pub fn classical(&self, point: &Point) -> Option<Mark> {
+        self.classical[point.x][point.y]
+    }
+
+    /// The spooky marks in `point`
+    pub fn spooky(&self, point: &Point) -> Vec<Mark> {
+        self.spooky
+            .iter()
+            .filter(|(_, a, b)| a == point || b == point)
+            .map(|(mark, _, _)| *mark)
+            .collect()
+    }
+
+    /// The mark that closed a cycle and the two squares the player to move chooses between
+    pub fn pending_collapse(&self) -> Option<(Mark, Point, Point)> {
+        self.pending_collapse
+    }
+
+    /// Every move that can be played next, in a stable order
+    pub fn legal_moves(&self) -> Vec<QuantumMove> {
+        if self.result.is_some() {
+            return Vec::new();
+        }
+        if let Some((_, a, b)) = self.pending_collapse {
+            return vec![QuantumMove::Collapse(a), QuantumMove::Collapse(b)];
+        }
+        let open: Vec<Point> = squares()
+            .filter(|point| self.classical(point).is_none())
+            .collect();
+        match open[..] {
+            [point] => vec![QuantumMove::Place(point)],
+            _ => open
+                .iter()
+                .enumerate()
+                .flat_map(|(i, a)| open[i + 1..].iter().map(|b| QuantumMove::Entangle(*a, *b)))
+                .collect(),
+        }
+    }
+
+    /// Play `player_move` for the player to move
+    pub fn play(&mut self, player_move: &QuantumMove) -> Result<(), String> {
+        if let Some(result) = self.result {
+            return Err(format!("The game is over: {result}"));
+        }
+        let in_bounds = |point: &Point| point.x < Self::SIZE && point.y < Self::SIZE;
+        match (*player_move, self.pending_collapse) {
+            (QuantumMove::Collapse(choice), Some((mark, a, b))) => {
+                if choice != a && choice != b {
+                    return Err(format!("{mark} can only collapse into {a} or {b}"));
+                }
+                self.pending_collapse = None;
+                self.collapse(mark, choice);
+                self.result = self.find_result();
+            }
+            (_, Some((mark, a, b))) => {
+                return Err(format!(
+                    "The last move made a cycle, so first choose whether {mark} collapses into {a} or {b}"
+                ));
+            }
+            (QuantumMove::Collapse(_), None) => {
+                return Err("There is no cycle to collapse".to_string());
+            }
+            (QuantumMove::Entangle(a, b), None) => {
+                if !in_bounds(&a) || !in_bounds(&b) {
+                    return Err(format!("Not squares of the board: {a} and {b}"));
+                }
+                if a == b {
+                    return Err("The two spooky marks have to go in different squares".to_string());
+                }
+                if let Some(point) = [a, b]
+                    .into_iter()
+                    .find(|point| self.classical(point).is_some())
+                {
+                    return Err(format!("{point} already has a classical mark"));
+                }
+                let closes_cycle = self.entangled(&a, &b);
+                let mark = self.next_mark();
+                self.spooky.push((mark, a, b));
+                self.to_move = self.to_move.other();
+                if closes_cycle {
+                    self.pending_collapse = Some((mark, a, b));
+                }
+            }
+            (QuantumMove::Place(point), None) => {
+                if self.legal_moves() != vec![QuantumMove::Place(point)] {
+                    return Err(format!(
+                        "A single classical mark can only be placed in the last open square, not {point}"
+                    ));
+                }
+                let mark = self.next_mark();
+                self.classical[point.x][point.y] = Some(mark);
+                self.to_move = self.to_move.other();
+                self.result = self.find_result();
+            }
+        }
+        if self.result.is_none() && self.legal_moves().is_empty() {
+            self.result = Some(QuantumResult::Draw);
+        }
+        Ok(())
+    }
+
+    fn next_mark(&mut self) -> Mark {
+        let mark = Mark {
+            player: self.to_move,
+            subscript: self.turn,
+        };
+        self.turn += 1;
+        mark
+    }
+
+    // Whether `a` and `b` are already linked by a chain of spooky marks, so that another spooky
+    // mark between them closes a cycle
+    fn entangled(&self, a: &Point, b: &Point) -> bool {
+        let mut reached = vec![*a];
+        let mut queue = VecDeque::from([*a]);
+        while let Some(point) = queue.pop_front() {
+            if point == *b {
+                return true;
+            }
+            for (_, one, other) in &self.spooky {
+                let next = match point {
+                    p if p == *one => other,
+                    p if p == *other => one,
+                    _ => continue,
+                };
+                if !reached.contains(next) {
+                    reached.push(*next);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        false
+    }
+
+    // Make `mark` classical at `point`. Every other spooky mark in `point` is then forced into its
+    // other square, and so on through everything entangled with it.
+    fn collapse(&mut self, mark: Mark, point: Point) {
+        let mut queue = VecDeque::from([(mark, point)]);
+        while let Some((mark, point)) = queue.pop_front() {
+            self.spooky.retain(|(spooky, _, _)| *spooky != mark);
+            self.classical[point.x][point.y] = Some(mark);
+            let (forced, rest): (Vec<_>, Vec<_>) = self
+                .spooky
+                .iter()
+                .partition(|(_, a, b)| *a == point || *b == point);
+            self.spooky = rest;
+            for (forced_mark, a, b) in forced {
+                let other = if a == point { b } else { a };
+                queue.push_back((forced_mark, other));
+            }
+        }
+    }
+
+    // Lines of classical marks. If both players have one, the player whose line's highest
+    // subscript is lower finished first.
+    fn find_result(&self) -> Option<QuantumResult> {
+        let finished = |player: Player| {
+            lines()
+                .filter_map(|line| {
+                    let marks: Option<Vec<Mark>> =
+                        line.iter().map(|point| self.classical(point)).collect();
+                    marks.filter(|marks| marks.iter().all(|mark| mark.player == player))
+                })
+                .map(|marks| marks.iter().map(|mark| mark.subscript).max().unwrap_or(0))
+                .min()
+        };
+        match (finished(Player::X), finished(Player::O)) {
+            (None, None) => None,
+            (Some(_), None) => Some(QuantumResult::Win(Player::X)),
+            (None, Some(_)) => Some(QuantumResult::Win(Player::O)),
+            (Some(x), Some(o)) => Some(QuantumResult::SplitWin {
+                first: if x < o { Player::X } else { Player::O },
+            }),
+        }
+    }
+}
+
+/// Every line of the board
+fn lines() -> impl Iterator<Item = Vec<Point>> {
+    let n = Quantum::SIZE;
+    let rows = (0..n).map(move |x| (0..n).map(|y| Point { x, y }).collect());
+    let columns = (0..n).map(move |y| (0..n).map(|x| Point { x, y }).collect());
+    let diagonals = [
+        (0..n).map(|i| Point { x: i, y: i }).collect(),
+        (0..n).map(|i| Point { x: i, y: n - 1 - i }).collect(),
+    ];
+    rows.chain(columns).chain(diagonals)
+}
+
+fn squares() -> impl Iterator<Item = Point> {
+    (0..Quantum::SIZE).flat_map(|x| (0..Quantum::SIZE).map(move |y| Point { x, y }))
+}
+
+/// The classic board with each square showing its classical mark, or its spooky marks in lower
+/// case
+impl std::fmt::Display for Quantum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = |point: &Point| match self.classical(point) {
+            Some(mark) => mark.to_string(),
+            None => self
+                .spooky(point)
+                .iter()
+                .map(|mark| mark.to_string().to_lowercase())
+                .collect::<Vec<String>>()
+                .join(" "),
+        };
+        let width = squares().map(|point| text(&point).len()).max().unwrap_or(0);
+        let rows: Vec<String> = (0..Self::SIZE)
+            .map(|x| {
+                (0..Self::SIZE)
+                    .map(|y| format!(" {:^width$} ", text(&Point { x, y })))
+                    .collect::<Vec<String>>()
+                    .join("|")
+            })
+            .collect();
+        let separator = format!("\n{}\n", "-".repeat(Self::SIZE * (width + 3)));
+        write!(f, "{}", rows.join(&separator))
+    }
+}
+
+/// A finished game scores `WIN` for a point against nothing and half that for a point against
+/// half a point. Choosing how a cycle collapses is a move, and is followed by another move of the
+/// same player.
+impl GameVariant for Quantum {
+    type Move = QuantumMove;
+
+    fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    fn legal_moves(&self) -> Vec<QuantumMove> {
+        Quantum::legal_moves(self)
+    }
+
+    fn play(&mut self, player_move: &QuantumMove) -> Result<(), String> {
+        Quantum::play(self, player_move)
+    }
+
+    fn is_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    fn score(&self) -> i32 {
+        self.result.map_or(0, |result| {
+            let player = self.to_move;
+            let points =
+                result.half_points(&player) as i32 - result.half_points(&player.other()) as i32;
+            points * (WIN / 2)
+        })
+    }
+
+    // "x1 y1 x2 y2" for two spooky marks, and "x y" to collapse a cycle or to place the last mark
+    fn parse_move(&self, input: &str) -> Result<QuantumMove, String> {
+        let numbers = input
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|e| e.to_string())?;
+        match numbers[..] {
+            [x1, y1, x2, y2] => Ok(QuantumMove::Entangle(
+                Point { x: x1, y: y1 },
+                Point { x: x2, y: y2 },
+            )),
+            [x, y] if self.pending_collapse.is_some() => Ok(QuantumMove::Collapse(Point { x, y })),
+            [x, y] => Ok(QuantumMove::Place(Point { x, y })),
+            _ => Err(format!(
+                "Incorrect number of arguments. Expected 4 (x1 y1 x2 y2) or 2 (x y). Got {}",
+                numbers.len()
+            )),
+        }
+    }
+
+    fn status(&self) -> String {
+        self.result
+            .map_or_else(|| "StillPlaying".to_string(), |result| result.to_string())
+    }
+
+    fn note(&self) -> Option<String> {
+        self.pending_collapse.map(|(mark, a, b)| {
+            format!(
+                "{} made a cycle, so {} chooses whether {mark} collapses into {a} or {b}",
+                mark.player, self.to_move
+            )
+        })
+    }
+}
+
+#[rustfmt::skip]
+impl Quantum {
+    const INTRO_MESSAGE: &'static str =
+        "\nWelcome to Quantum Tic-Tac-Toe!\
+         \n-------------------------------\n\
+         \n\
+         Each move puts two spooky marks, numbered by the move, in two different squares\n\
+         (e.g., \"0 0 1 1\"). Once the spooky marks make a cycle, the player who didn't\n\
+         make it chooses which of its two squares the last mark collapses into (e.g.,\n\
+         \"1 1\"), and everything entangled with it collapses into classical marks.\n\
+         Only classical marks make lines. If both players get a line in the same\n\
+         collapse, the one whose line has the lower highest number scores 1 and the\n\
+         other 1/2. Enter \"hint\" to see the computer's suggestion.\n\
+         \n\
+         ##################################################\n";
+}
+
+impl Quantum {
+    /// Play quantum tic-tac-toe against the computer
+    pub fn run(config: &Config) {
+        let mut engine = Negamax::new(config.depth.unwrap_or(Self::DEFAULT_DEPTH));
+        let new_game = |first, _| Quantum::new(first);
+        game_variant::run(config, Self::INTRO_MESSAGE, new_game, &mut engine);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_variant::VariantEngine;
+
+    fn mark(player: Player, subscript: u32) -> Mark {
+        Mark { player, subscript }
+    }
+
+    #[test]
+    fn cycles_collapse_into_classical_marks() {
+        let mut game = Quantum::new(Player::X);
+        let (a, b, c) = (
+            Point { x: 0, y: 0 },
+            Point { x: 0, y: 1 },
+            Point { x: 1, y: 1 },
+        );
+        game.play(&QuantumMove::Entangle(a, b)).unwrap();
+        game.play(&QuantumMove::Entangle(b, c)).unwrap();
+        assert_eq!(game.pending_collapse(), None);
+        assert_eq!(
+            game.spooky(&b),
+            vec![mark(Player::X, 1), mark(Player::O, 2)]
+        );
+
+        // X3 closes the cycle a - b - c - a, so O chooses where it goes
+        game.play(&QuantumMove::Entangle(c, a)).unwrap();
+        assert_eq!(game.to_move(), Player::O);
+        assert_eq!(game.pending_collapse(), Some((mark(Player::X, 3), c, a)));
+        assert!(game.play(&QuantumMove::Entangle(a, b)).is_err());
+        assert!(game.play(&QuantumMove::Collapse(b)).is_err());
+
+        // X3 in a forces X1 into b, which forces O2 into c
+        game.play(&QuantumMove::Collapse(a)).unwrap();
+        assert_eq!(game.classical(&a), Some(mark(Player::X, 3)));
+        assert_eq!(game.classical(&b), Some(mark(Player::X, 1)));
+        assert_eq!(game.classical(&c), Some(mark(Player::O, 2)));
+        assert!(game.spooky.is_empty());
+        assert_eq!(game.to_move(), Player::O);
+        assert_eq!(game.result(), None);
+    }
+
+    #[test]
+    fn simultaneous_lines_are_scored_by_their_subscripts() {
+        let mut game = Quantum::new(Player::X);
+        for (y, (x_mark, o_mark)) in [(1, 2), (3, 4), (7, 8)].into_iter().enumerate() {
+            game.classical[0][y] = Some(mark(Player::X, x_mark));
+            game.classical[1][y] = Some(mark(Player::O, o_mark));
+        }
+        let result = game.find_result().unwrap();
+        assert_eq!(result, QuantumResult::SplitWin { first: Player::X });
+        assert_eq!(result.half_points(&Player::X), 2);
+        assert_eq!(result.half_points(&Player::O), 1);
+
+        // O's line finishes first if its highest subscript is lower
+        game.classical[0][2] = Some(mark(Player::X, 9));
+        game.classical[1][2] = Some(mark(Player::O, 6));
+        assert_eq!(
+            game.find_result(),
+            Some(QuantumResult::SplitWin { first: Player::O })
+        );
+    }
+
+    #[test]
+    fn engine_collapses_a_cycle_in_its_favour() {
+        // O6 closed a cycle with X5. Collapsing O6 into (1, 2) would finish O's middle row, so X
+        // puts it in (2, 0) instead.
+        let mut game = Quantum::new(Player::X);
+        game.classical[0][0] = Some(mark(Player::X, 1));
+        game.classical[1][0] = Some(mark(Player::O, 2));
+        game.classical[2][2] = Some(mark(Player::X, 3));
+        game.classical[1][1] = Some(mark(Player::O, 4));
+        let (a, b) = (Point { x: 1, y: 2 }, Point { x: 2, y: 0 });
+        game.spooky = vec![(mark(Player::X, 5), a, b), (mark(Player::O, 6), a, b)];
+        game.pending_collapse = Some((mark(Player::O, 6), a, b));
+        game.turn = 7;
+
+        let report = Negamax::new(2).choose_move(&game);
+        assert_eq!(report.player_move, QuantumMove::Collapse(b));
+    }
+}
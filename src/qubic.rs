@@ -0,0 +1,497 @@
+use crate::board_info::{Player, SquareType};
+use crate::config::Config;
+use crate::game_variant::{self, GameVariant, Negamax, WIN};
+use crate::scoring::GameStatus;
+
+/// The coordinates of a cell, one per dimension. For a cube they are the row, the column and the
+/// layer, in that order.
+pub type Coordinates = Vec<usize>;
+
+/// A move in a `Cube`: the cell to play in. Written the way it is entered, e.g. "0 1 2".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell(pub Coordinates);
+
+impl std::fmt::Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let coordinates: Vec<String> = self.0.iter().map(usize::to_string).collect();
+        write!(f, "{}", coordinates.join(" "))
+    }
+}
+
+/// Tic-tac-toe in more than two dimensions. The classic game is Qubic: a 4x4x4 cube, won by
+/// completing any of its 76 straight lines, including the ones through layers and the four
+/// diagonals through the centre of the cube.
+#[derive(Debug, Clone)]
+pub struct Cube {
+    size: usize,
+    dimensions: usize,
+    cells: Vec<SquareType>,
+    /// Every straight line through the cube, as cell indices
+    lines: Vec<Vec<usize>>,
+    /// The indices of the lines through each cell
+    lines_through: Vec<Vec<usize>>,
+    blank_cells: usize,
+    game_status: GameStatus,
+    to_move: Player,
+}
+
+impl Cube {
+    /// The width of the classic 4x4x4 game
+    pub const SIZE: usize = 4;
+    /// How many plies the computer searches when `--depth` isn't given
+    pub const DEFAULT_DEPTH: u32 = 4;
+
+    /// An empty cube with sides of `size` cells, where `first` moves first
+    pub fn new(size: usize, first: Player) -> Self {
+        Self::with_dimensions(size, 3, first)
+    }
+
+    /// An empty board with sides of `size` cells in `dimensions` dimensions. Two dimensions give
+    /// an ordinary tic-tac-toe board.
+    pub fn with_dimensions(size: usize, dimensions: usize, first: Player) -> Self {
+        let cell_count = size.pow(dimensions as u32);
+        let lines: Vec<Vec<usize>> = lines(size, dimensions)
+            .iter()
+            .map(|line| line.iter().map(|cell| index(cell, size)).collect())
+            .collect();
+        let mut lines_through = vec![Vec::new(); cell_count];
+        for (line_index, line) in lines.iter().enumerate() {
+            for cell in line {
+                lines_through[*cell].push(line_index);
+            }
+        }
+        Self {
+            size,
+            dimensions,
+            cells: vec![SquareType::B; cell_count],
+            lines,
+            lines_through,
+            blank_cells: cell_count,
+            game_status: GameStatus::StillPlaying,
+            to_move: first,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn game_status(&self) -> GameStatus {
+        self.game_status
+    }
+
+    pub fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    /// The number of winning lines through the cube
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn cell(&self, coordinates: &[usize]) -> SquareType {
+        self.cells[index(coordinates, self.size)]
+    }
+
+    /// The blank cells, in a stable order
+    pub fn blank_cells(&self) -> Vec<Coordinates> {
+        (0..self.cells.len())
+            .filter(|cell| self.cells[*cell] == SquareType::B)
+            .map(|cell| coordinates(cell, self.size, self.dimensions))
+            .collect()
+    }
+
+    /// The player to move places a piece in the cell at `coordinates`
+    pub fn play(&mut self, coordinates: &[usize]) -> Result<(), String> {
+        if self.game_status != GameStatus::StillPlaying {
+            return Err("The game is over".to_string());
+        }
+        if coordinates.len() != self.dimensions
+            || coordinates
+                .iter()
+                .any(|coordinate| *coordinate >= self.size)
+        {
+            return Err(format!(
+                "Not a valid cell: {coordinates:?}. Give {} numbers from 0 to {}",
+                self.dimensions,
+                self.size - 1
+            ));
+        }
+        let cell = index(coordinates, self.size);
+        if self.cells[cell] != SquareType::B {
+            return Err(format!("The cell {coordinates:?} has already been played"));
+        }
+
+        let player = self.to_move;
+        self.cells[cell] = player.square_type();
+        self.blank_cells -= 1;
+        self.to_move = player.other();
+        // Only the lines through the new piece can have been completed
+        let completed = self.lines_through[cell].iter().any(|line| {
+            self.lines[*line]
+                .iter()
+                .all(|cell| self.cells[*cell] == player.square_type())
+        });
+        if completed {
+            self.game_status = match player {
+                Player::X => GameStatus::XWin,
+                Player::O => GameStatus::OWin,
+            };
+        } else if self.blank_cells == 0 {
+            self.game_status = GameStatus::Draw;
+        }
+        Ok(())
+    }
+
+    /// How good the position looks for X (positive) or O (negative). Every line only one player
+    /// has pieces in counts for that player, ten times more for every extra piece. On big cubes
+    /// that adds up to more than a win is worth, so the estimate is kept to half of `WIN`.
+    pub fn evaluate(&self) -> i32 {
+        match self.game_status {
+            GameStatus::XWin => return WIN,
+            GameStatus::OWin => return -WIN,
+            GameStatus::Draw => return 0,
+            GameStatus::StillPlaying => (),
+        }
+        let score = self
+            .lines
+            .iter()
+            .map(|line| {
+                let count = |square_type| {
+                    line.iter()
+                        .filter(|cell| self.cells[**cell] == square_type)
+                        .count() as u32
+                };
+                match (count(SquareType::X), count(SquareType::O)) {
+                    (0, 0) => 0,
+                    (x, 0) => 10_i64.saturating_pow(x - 1),
+                    (0, o) => -10_i64.saturating_pow(o - 1),
+                    _ => 0,
+                }
+            })
+            .fold(0, i64::saturating_add);
+        let limit = i64::from(WIN / 2);
+        score.clamp(-limit, limit) as i32
+    }
+
+    // The cells that would complete a line for `player`
+    fn winning_cells(&self, player: &Player) -> Vec<usize> {
+        let mut cells: Vec<usize> = self
+            .lines
+            .iter()
+            .filter_map(|line| {
+                let mut blanks = line
+                    .iter()
+                    .filter(|cell| self.cells[**cell] == SquareType::B);
+                let blank = blanks.next()?;
+                let rest_are_own = blanks.next().is_none()
+                    && line.iter().all(|cell| {
+                        self.cells[*cell] == SquareType::B
+                            || self.cells[*cell] == player.square_type()
+                    });
+                rest_are_own.then_some(*blank)
+            })
+            .collect();
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+}
+
+/// Each layer drawn as a normal board, side by side
+impl std::fmt::Display for Cube {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let size = self.size;
+        let layers = self.cells.len() / (size * size);
+        let width = size * 4 - 1;
+        let mut lines = vec![String::new(); size * 2];
+        for layer in 0..layers {
+            let separator = if layer + 1 < layers { "    " } else { "" };
+            lines[0].push_str(&format!("{:<width$}{separator}", format!("Layer {layer}")));
+            for x in 0..size {
+                let row: Vec<String> = (0..size)
+                    .map(|y| format!(" {} ", self.cells[x + size * y + size * size * layer]))
+                    .collect();
+                lines[2 * x + 1].push_str(&format!("{}{separator}", row.join("|")));
+                if x + 1 < size {
+                    lines[2 * x + 2].push_str(&format!("{}{separator}", "-".repeat(width)));
+                }
+            }
+        }
+        let lines: Vec<&str> = lines.iter().map(|line| line.trim_end()).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+// The position of a cell in `Cube::cells`: the first coordinate varies fastest
+fn index(coordinates: &[usize], size: usize) -> usize {
+    coordinates
+        .iter()
+        .rev()
+        .fold(0, |index, coordinate| index * size + coordinate)
+}
+
+fn coordinates(mut index: usize, size: usize, dimensions: usize) -> Coordinates {
+    (0..dimensions)
+        .map(|_| {
+            let coordinate = index % size;
+            index /= size;
+            coordinate
+        })
+        .collect()
+}
+
+// Along each dimension a line either stays at one coordinate, counts up, or counts down, and at
+// least one dimension has to change. Walking the same line backwards gives the same line, so only
+// lines whose first changing dimension counts up are kept. In a d-dimensional cube with sides of
+// n cells that leaves ((n + 2)^d - n^d) / 2 lines.
+fn lines(size: usize, dimensions: usize) -> Vec<Vec<Coordinates>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Direction {
+        Fixed(usize),
+        Up,
+        Down,
+    }
+
+    let choices: Vec<Direction> = (0..size)
+        .map(Direction::Fixed)
+        .chain([Direction::Up, Direction::Down])
+        .collect();
+    let mut lines = Vec::new();
+    for combination in 0..choices.len().pow(dimensions as u32) {
+        let directions: Vec<Direction> = coordinates(combination, choices.len(), dimensions)
+            .into_iter()
+            .map(|choice| choices[choice])
+            .collect();
+        if directions
+            .iter()
+            .find(|direction| !matches!(direction, Direction::Fixed(_)))
+            != Some(&Direction::Up)
+        {
+            continue;
+        }
+        lines.push(
+            (0..size)
+                .map(|step| {
+                    directions
+                        .iter()
+                        .map(|direction| match direction {
+                            Direction::Fixed(coordinate) => *coordinate,
+                            Direction::Up => step,
+                            Direction::Down => size - 1 - step,
+                        })
+                        .collect()
+                })
+                .collect(),
+        );
+    }
+    lines
+}
+
+/// The computer searches every blank cell, those on the most lines first, except that it always
+/// takes a win and otherwise only looks at blocking the opponent's threats when there are any
+impl GameVariant for Cube {
+    type Move = Cell;
+
+    fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    fn legal_moves(&self) -> Vec<Cell> {
+        if self.game_status != GameStatus::StillPlaying {
+            return Vec::new();
+        }
+        self.blank_cells().into_iter().map(Cell).collect()
+    }
+
+    fn search_moves(&self) -> Vec<Cell> {
+        let cells = match (
+            self.winning_cells(&self.to_move).first(),
+            self.winning_cells(&self.to_move.other()),
+        ) {
+            (Some(win), _) => vec![*win],
+            (None, blocks) if !blocks.is_empty() => blocks,
+            (None, _) => {
+                let mut cells: Vec<usize> = (0..self.cells.len())
+                    .filter(|cell| self.cells[*cell] == SquareType::B)
+                    .collect();
+                cells.sort_by_key(|cell| std::cmp::Reverse(self.lines_through[*cell].len()));
+                cells
+            }
+        };
+        cells
+            .into_iter()
+            .map(|cell| Cell(coordinates(cell, self.size, self.dimensions)))
+            .collect()
+    }
+
+    fn play(&mut self, player_move: &Cell) -> Result<(), String> {
+        Cube::play(self, &player_move.0)
+    }
+
+    fn is_over(&self) -> bool {
+        self.game_status != GameStatus::StillPlaying
+    }
+
+    fn status(&self) -> String {
+        format!("{:?}", self.game_status)
+    }
+
+    fn score(&self) -> i32 {
+        match self.to_move {
+            Player::X => self.evaluate(),
+            Player::O => -self.evaluate(),
+        }
+    }
+
+    // A move is "x y z"
+    fn parse_move(&self, input: &str) -> Result<Cell, String> {
+        let numbers = input
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Coordinates, _>>()
+            .map_err(|e| e.to_string())?;
+        if numbers.len() == self.dimensions {
+            return Ok(Cell(numbers));
+        }
+        // x, y and z as far as they go, and numbered coordinates for more dimensions than that
+        let names: Vec<String> = if self.dimensions <= 3 {
+            ["x", "y", "z"][..self.dimensions]
+                .iter()
+                .map(|name| name.to_string())
+                .collect()
+        } else {
+            (1..=self.dimensions).map(|i| format!("x{i}")).collect()
+        };
+        Err(format!(
+            "Incorrect number of arguments. Expected {} ({}). Got {}",
+            self.dimensions,
+            names.join(" "),
+            numbers.len()
+        ))
+    }
+}
+
+#[rustfmt::skip]
+impl Cube {
+    const INTRO_MESSAGE: &'static str =
+        "\nWelcome to 3D Tic-Tac-Toe!\
+         \n--------------------------\n\
+         \n\
+         The board is a cube, drawn one layer at a time. Complete a straight line in\n\
+         any direction to win: along a layer, straight down through the layers, or\n\
+         diagonally through them.\n\
+         \n\
+         Play by entering the row, the column and the layer of the cell you want to\n\
+         play in, separated by spaces (e.g., \"0 1 2\").\n\
+         Enter \"hint\" to see the computer's suggestion.\n\
+         \n\
+         ##################################################\n";
+}
+
+impl Cube {
+    /// Play a game of 3D tic-tac-toe against the computer
+    pub fn run(config: &Config) {
+        let mut engine = Negamax::new(config.depth.unwrap_or(Self::DEFAULT_DEPTH));
+        let size = config.board_size;
+        let new_game = |first, _| Cube::new(size, first);
+        game_variant::run(config, Self::INTRO_MESSAGE, new_game, &mut engine);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_variant::VariantEngine;
+
+    #[test]
+    fn line_counts() {
+        assert_eq!(Cube::with_dimensions(3, 2, Player::X).line_count(), 8);
+        assert_eq!(Cube::new(3, Player::X).line_count(), 49);
+        assert_eq!(Cube::new(4, Player::X).line_count(), 76);
+        assert_eq!(Cube::with_dimensions(3, 4, Player::X).line_count(), 272);
+    }
+
+    #[test]
+    fn lines_through_the_layers_win() {
+        let mut cube = Cube::new(4, Player::O);
+        // A space diagonal from one corner of the cube to the opposite one
+        for step in 0..3 {
+            cube.play(&[step, step, step]).unwrap();
+            cube.play(&[step, 3, 0]).unwrap();
+            assert_eq!(cube.game_status(), GameStatus::StillPlaying);
+        }
+        assert!(cube.play(&[0, 0, 0]).is_err());
+        cube.play(&[3, 3, 3]).unwrap();
+        assert_eq!(cube.game_status(), GameStatus::OWin);
+        assert!(cube.play(&[3, 2, 1]).is_err());
+    }
+
+    #[test]
+    fn engine_wins_and_blocks() {
+        let mut cube = Cube::new(4, Player::X);
+        for step in 0..3 {
+            cube.play(&[1, 2, step]).unwrap();
+            cube.play(&[0, step, 0]).unwrap();
+        }
+        // X to move takes its own line rather than blocking O's
+        let report = Negamax::new(2).choose_move(&cube);
+        assert_eq!(report.player_move, Cell(vec![1, 2, 3]));
+        assert_eq!(report.score, Some(WIN - 1));
+
+        // O to move has no win of its own, so blocks X
+        let mut cube = Cube::new(4, Player::X);
+        for (x, o) in [([1, 2, 0], [0, 0, 0]), ([1, 2, 1], [3, 3, 3])] {
+            cube.play(&x).unwrap();
+            cube.play(&o).unwrap();
+        }
+        cube.play(&[1, 2, 2]).unwrap();
+        assert_eq!(cube.search_moves(), vec![Cell(vec![1, 2, 3])]);
+        let report = Negamax::new(2).choose_move(&cube);
+        assert_eq!(report.player_move, Cell(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn layers_are_drawn_side_by_side() {
+        let mut cube = Cube::new(2, Player::X);
+        cube.play(&[0, 1, 1]).unwrap();
+        assert_eq!(
+            cube.to_string(),
+            "Layer 0    Layer 1\n   |          | X\n-------    -------\n   |          |"
+        );
+        assert_eq!(Cell(vec![0, 1, 1]).to_string(), "0 1 1");
+    }
+
+    #[test]
+    fn long_lines_on_big_cubes_stay_below_a_win() {
+        let size = 12;
+        let mut cube = Cube::new(size, Player::X);
+        // X fills all but one cell of a line, while O's pieces are spread out
+        for step in 0..size - 1 {
+            cube.play(&[0, 0, step]).unwrap();
+            cube.play(&[size - 1, step, 5 * step % size]).unwrap();
+        }
+        assert_eq!(cube.game_status(), GameStatus::StillPlaying);
+        assert_eq!(cube.evaluate(), WIN / 2);
+    }
+
+    #[test]
+    fn moves_need_a_coordinate_for_every_dimension() {
+        let cube = Cube::new(3, Player::X);
+        assert_eq!(cube.parse_move("0 1 2"), Ok(Cell(vec![0, 1, 2])));
+        assert_eq!(
+            cube.parse_move("0 1"),
+            Err("Incorrect number of arguments. Expected 3 (x y z). Got 2".to_string())
+        );
+        let board = Cube::with_dimensions(3, 2, Player::X);
+        assert_eq!(
+            board.parse_move("0 1 2"),
+            Err("Incorrect number of arguments. Expected 2 (x y). Got 3".to_string())
+        );
+        let hypercube = Cube::with_dimensions(3, 4, Player::X);
+        assert_eq!(
+            hypercube.parse_move("0"),
+            Err("Incorrect number of arguments. Expected 4 (x1 x2 x3 x4). Got 1".to_string())
+        );
+    }
+}
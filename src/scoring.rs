@@ -1,5 +1,5 @@
-use crate::board_info::{Point, SquareType};
-use crate::tic_tac_toe_board::Board;
+use crate::board_info::{Player, Point, SquareType};
+use std::collections::BTreeMap;
 
 /// `PartialLineStatus` is used in scoring a particular line (i.e., row, column, or diagonal)
 /// of the tic-tac-toe board. All of the lines of the board combined give the total `GameStatus`.
@@ -15,7 +15,8 @@ impl<'a> PartialLineStatus<'a> {
     /// give is that the `GameStatus` is either a `Draw` (i.e., `PartialDraw`) or `StillPlaying`
     /// (i.e., `PartialLine(SquareType::B)`). If the line is filled (i.e., no blank squares) then the
     /// `PartialLineStatus` is `PartialDraw`. Otherwise, if there are blank squares in the line the
-    /// `PartialLineStatus` is `PartialLine(SquareType::B)`
+    /// `PartialLineStatus` is `PartialLine(SquareType::B)`. A blocked square can't be won through,
+    /// so it counts the same as a line both players have played in.
     ///
     /// `PartialLineStatus::combine()` is intended to be used within a `reduce` method of an iterator.
     pub fn combine(lhs: &Self, rhs: &Self) -> Self {
@@ -23,23 +24,25 @@ impl<'a> PartialLineStatus<'a> {
 
         match lhs {
             Self::PartialLine(S::B) => match rhs {
-                Self::PartialLine(S::B | S::O | S::X) | Self::PartialDraw => {
+                Self::PartialLine(S::B | S::O | S::X | S::Blocked) | Self::PartialDraw => {
                     Self::PartialLine(&S::B)
                 }
             },
             Self::PartialLine(S::O) => match rhs {
                 Self::PartialLine(S::B) => Self::PartialLine(&S::B),
                 Self::PartialLine(S::O) => Self::PartialLine(&S::O),
-                Self::PartialLine(S::X) | Self::PartialDraw => Self::PartialDraw,
+                Self::PartialLine(S::X | S::Blocked) | Self::PartialDraw => Self::PartialDraw,
             },
             Self::PartialLine(S::X) => match rhs {
                 Self::PartialLine(S::B) => Self::PartialLine(&S::B),
-                Self::PartialLine(S::O) | Self::PartialDraw => Self::PartialDraw,
+                Self::PartialLine(S::O | S::Blocked) | Self::PartialDraw => Self::PartialDraw,
                 Self::PartialLine(S::X) => Self::PartialLine(&S::X),
             },
-            Self::PartialDraw => match rhs {
+            Self::PartialLine(S::Blocked) | Self::PartialDraw => match rhs {
                 Self::PartialLine(S::B) => Self::PartialLine(&S::B),
-                Self::PartialLine(S::O | S::X) | Self::PartialDraw => Self::PartialDraw,
+                Self::PartialLine(S::O | S::X | S::Blocked) | Self::PartialDraw => {
+                    Self::PartialDraw
+                }
             },
         }
     }
@@ -52,7 +55,7 @@ impl<'a> PartialLineStatus<'a> {
             Self::PartialLine(SquareType::B) => GameStatus::StillPlaying,
             Self::PartialLine(SquareType::O) => GameStatus::OWin,
             Self::PartialLine(SquareType::X) => GameStatus::XWin,
-            Self::PartialDraw => GameStatus::Draw,
+            Self::PartialLine(SquareType::Blocked) | Self::PartialDraw => GameStatus::Draw,
         }
     }
 }
@@ -119,6 +122,9 @@ pub struct MoveScoreTurns {
     pub score: GameStatus,
     pub blank_squares_remaining: u32,
     pub player_move: Point,
+    /// The piece placed at `player_move`. This is the player's own piece except in Wild
+    /// tic-tac-toe, where either may be chosen.
+    pub piece: SquareType,
 }
 
 /// Since the alpha-beta pruning algorithm is a direct upgrade from the naive minmax algorithm,
@@ -134,6 +140,20 @@ pub struct MoveScoreTurns {
 ///
 /// Move A < Move B when `score` == `OWin`
 /// Move A > Move B when `score` == `XWin`.
+///
+/// Under misère rules `Board` reports a completed line as a win for the *other* player, so the same
+/// ordering makes each player prefer the slowest loss (completing a line of their own as late as
+/// possible) and the fastest win (the opponent being forced to complete a line soonest).
+///
+/// `Draw` and `StillPlaying` are deliberately ranked the same. An exhaustive search only ever
+/// scores finished games, so `StillPlaying` is only compared when a depth-limited search stops
+/// early. Scoring such an unknown position as neither side winning keeps the ordering symmetric
+/// between the X and O players, which `alpha_beta()` relies on when it flips perspective: ranking
+/// it above a draw would have X chase positions it couldn't see the end of while O settled for
+/// the draw.
+///
+/// The ordering only looks at how good a result is, so two different moves can compare `Equal`
+/// without being `==`. Use `cmp()` or `cmp_for()` when only the ordering matters.
 impl Ord for MoveScoreTurns {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         type GS = GameStatus;
@@ -147,13 +167,14 @@ impl Ord for MoveScoreTurns {
                 .blank_squares_remaining
                 .cmp(&other.blank_squares_remaining), // The higher number of `blank_squares_remaining` should be `Greater` in this case
 
-            (GS::Draw | GS::StillPlaying, GS::OWin)
-            | (GS::XWin, GS::Draw | GS::StillPlaying | GS::OWin) => std::cmp::Ordering::Greater,
-
-            (GS::Draw | GS::StillPlaying, GS::Draw | GS::StillPlaying) => std::cmp::Ordering::Equal,
-
-            (GS::OWin, GS::Draw | GS::StillPlaying | GS::XWin)
-            | (GS::Draw | GS::StillPlaying, GS::XWin) => std::cmp::Ordering::Less,
+            (score, other_score) => {
+                let rank = |score| match score {
+                    GS::OWin => 0,
+                    GS::Draw | GS::StillPlaying => 1,
+                    GS::XWin => 2,
+                };
+                rank(score).cmp(&rank(other_score))
+            }
         }
     }
 }
@@ -179,15 +200,101 @@ impl MoveScoreTurns {
     pub const MAX: Self = Self {
         score: GameStatus::XWin,
         player_move: Point { x: 0, y: 0 },
-        blank_squares_remaining: Board::EMPTY_BOARD_BLANK_SQUARES_REMAINING,
+        blank_squares_remaining: u32::MAX,
+        piece: SquareType::B,
     };
 
     /// The absolute minimum a `MoveScoreTurns` instace can have
     pub const MIN: Self = Self {
         score: GameStatus::OWin,
         player_move: Point { x: 0, y: 0 },
-        blank_squares_remaining: Board::EMPTY_BOARD_BLANK_SQUARES_REMAINING,
+        blank_squares_remaining: u32::MAX,
+        piece: SquareType::B,
     };
+
+    /// The worst possible result for `player`
+    pub fn worst_for(player: &Player) -> Self {
+        match player {
+            Player::X => Self::MIN,
+            Player::O => Self::MAX,
+        }
+    }
+
+    /// Compare two results from the point of view of `player`, so that `Greater` always means
+    /// "better for `player`". This is what lets `alpha_beta()` treat both players the same way.
+    pub fn cmp_for(&self, other: &Self, player: &Player) -> std::cmp::Ordering {
+        match player {
+            Player::X => self.cmp(other),
+            Player::O => other.cmp(self),
+        }
+    }
+}
+
+/// The running score of a match against the computer. Every game is counted by its `GameStatus`
+/// and, since the user doesn't always play the same piece, by whether the user won or lost it.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Scoreboard {
+    pub by_status: BTreeMap<GameStatus, u32>,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Scoreboard {
+    /// Count a game that ended in `game_status` with the user playing `user`
+    pub fn record(&mut self, game_status: GameStatus, user: &Player) {
+        *self.by_status.entry(game_status).or_default() += 1;
+        match (game_status, user) {
+            (GameStatus::XWin, Player::X) | (GameStatus::OWin, Player::O) => self.wins += 1,
+            (GameStatus::XWin, Player::O) | (GameStatus::OWin, Player::X) => self.losses += 1,
+            (GameStatus::Draw | GameStatus::StillPlaying, _) => self.draws += 1,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Whether the match is over with `remaining` games left to play: either none are left, or one
+    /// side has won more games than the other could still catch up
+    pub fn is_decided(&self, remaining: u32) -> bool {
+        remaining == 0 || self.wins > self.losses + remaining || self.losses > self.wins + remaining
+    }
+
+    /// Who won the match and by how much
+    pub fn summary(&self) -> String {
+        let result = match self.wins.cmp(&self.losses) {
+            std::cmp::Ordering::Greater => "You win the match",
+            std::cmp::Ordering::Less => "The computer wins the match",
+            std::cmp::Ordering::Equal => "The match is drawn",
+        };
+        format!(
+            "{result} {}-{} after {} games, with {} drawn",
+            self.wins.max(self.losses),
+            self.wins.min(self.losses),
+            self.games(),
+            self.draws
+        )
+    }
+}
+
+/// e.g. "You 2, Computer 1, Draws 1 (OWin: 1, Draw: 1, XWin: 2)"
+impl std::fmt::Display for Scoreboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let by_status: Vec<String> = self
+            .by_status
+            .iter()
+            .map(|(game_status, count)| format!("{game_status}: {count}"))
+            .collect();
+        write!(
+            f,
+            "You {}, Computer {}, Draws {} ({})",
+            self.wins,
+            self.losses,
+            self.draws,
+            by_status.join(", ")
+        )
+    }
 }
 
 #[cfg(test)]
@@ -317,4 +424,82 @@ mod tests {
 
         assert!(xwin_more_blank_squares > xwin_less_blank_squares);
     }
+
+    #[test]
+    fn draw_ranks_with_stillplaying() {
+        let draw = MoveScoreTurns {
+            score: GameStatus::Draw,
+            blank_squares_remaining: 0,
+            ..Default::default()
+        };
+
+        let stillplaying = MoveScoreTurns {
+            score: GameStatus::StillPlaying,
+            blank_squares_remaining: 4,
+            player_move: Point { x: 1, y: 1 },
+            ..Default::default()
+        };
+
+        assert_eq!(draw.cmp(&stillplaying), std::cmp::Ordering::Equal);
+        assert_eq!(
+            stillplaying.cmp_for(&draw, &Player::X),
+            std::cmp::Ordering::Equal
+        );
+        assert_eq!(
+            stillplaying.cmp_for(&draw, &Player::O),
+            std::cmp::Ordering::Equal
+        );
+        assert_ne!(draw, stillplaying);
+
+        // Equally good results for different moves are ordered equal but aren't the same
+        let other_draw = MoveScoreTurns {
+            player_move: Point { x: 2, y: 2 },
+            ..draw
+        };
+        assert_eq!(draw.cmp(&other_draw), std::cmp::Ordering::Equal);
+        assert_ne!(draw, other_draw);
+    }
+
+    #[test]
+    fn cmp_for_o_is_reversed() {
+        let xwin = MoveScoreTurns {
+            score: GameStatus::XWin,
+            ..Default::default()
+        };
+
+        let owin = MoveScoreTurns {
+            score: GameStatus::OWin,
+            ..Default::default()
+        };
+
+        assert_eq!(owin.cmp_for(&xwin, &Player::O), std::cmp::Ordering::Greater);
+        assert_eq!(owin.cmp_for(&xwin, &Player::X), std::cmp::Ordering::Less);
+    }
+    #[test]
+    fn scoreboard_counts_games_from_the_users_side() {
+        let mut scoreboard = Scoreboard::default();
+        scoreboard.record(GameStatus::XWin, &Player::X);
+        scoreboard.record(GameStatus::XWin, &Player::O);
+        scoreboard.record(GameStatus::OWin, &Player::O);
+        scoreboard.record(GameStatus::Draw, &Player::X);
+        assert_eq!(
+            (scoreboard.wins, scoreboard.draws, scoreboard.losses),
+            (2, 1, 1)
+        );
+        assert_eq!(scoreboard.by_status[&GameStatus::XWin], 2);
+        assert_eq!(
+            scoreboard.to_string(),
+            "You 2, Computer 1, Draws 1 (OWin: 1, Draw: 1, XWin: 2)"
+        );
+        assert_eq!(
+            scoreboard.summary(),
+            "You win the match 2-1 after 4 games, with 1 drawn"
+        );
+
+        // One game ahead can still be caught with a game to go, but not two
+        assert!(!scoreboard.is_decided(1));
+        assert!(scoreboard.is_decided(0));
+        scoreboard.record(GameStatus::OWin, &Player::O);
+        assert!(scoreboard.is_decided(1));
+    }
 }
@@ -0,0 +1,471 @@
+use crate::board_info::{Player, Point, SquareType};
+use crate::scoring::{GameStatus, MoveScoreTurns};
+use crate::search_tree::SearchTree;
+use crate::tic_tac_toe_board::Board;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Counters collected while running `alpha_beta()`. Comparing them for the same position with and
+/// without move ordering shows how much work the ordering saves.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchStats {
+    /// The number of positions `alpha_beta()` was called on
+    pub nodes: u64,
+    /// The number of times the remaining moves of a position were pruned
+    pub cutoffs: u64,
+    /// The number of plies below the root of the deepest position visited
+    pub max_depth: u32,
+}
+
+impl SearchStats {
+    /// Add the counts of another (e.g., another thread's) search to these ones
+    pub fn merge(&mut self, other: &Self) {
+        self.nodes += other.nodes;
+        self.cutoffs += other.cutoffs;
+        self.max_depth = self.max_depth.max(other.max_depth);
+    }
+}
+
+impl std::fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} nodes, {} cutoffs, depth {}",
+            self.nodes, self.cutoffs, self.max_depth
+        )
+    }
+}
+
+/// The line of play both players are expected to follow from a position, as found by a search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrincipalVariation {
+    /// The player making the first move of the line. After that the players alternate.
+    pub player: Player,
+    /// The square of each move and the piece placed there, which under wild rules needn't be the
+    /// mover's own
+    pub moves: Vec<(Point, SquareType)>,
+    /// How the game stands at the end of the line, when the search looked that far
+    pub score: Option<GameStatus>,
+}
+
+/// Renders the line as, for example, "X (1, 1) → O (0, 0) → X (2, 2) ... XWin in 3". A move
+/// placing the other player's piece, as wild rules allow, is followed by that piece, e.g.
+/// "O (0, 2) X".
+impl std::fmt::Display for PrincipalVariation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut player = self.player;
+        let line = self
+            .moves
+            .iter()
+            .map(|(point, piece)| {
+                let step = if *piece == player.square_type() {
+                    format!("{player} {point}")
+                } else {
+                    format!("{player} {point} {piece}")
+                };
+                player = player.other();
+                step
+            })
+            .collect::<Vec<String>>()
+            .join(" → ");
+        write!(f, "{line}")?;
+        if let Some(score) = self.score {
+            write!(f, " ... {score} in {}", self.moves.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of a complete search: the best move with its score, and the principal variation
+/// that explains *why* that move is the best one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub move_score_turns: MoveScoreTurns,
+    pub principal_variation: PrincipalVariation,
+}
+
+impl SearchResult {
+    pub fn new(
+        player: Player,
+        move_score_turns: MoveScoreTurns,
+        line: &[(Point, SquareType)],
+    ) -> Self {
+        Self {
+            move_score_turns,
+            principal_variation: PrincipalVariation {
+                player,
+                moves: line.to_vec(),
+                score: Some(move_score_turns.score),
+            },
+        }
+    }
+}
+
+// The heuristics used to decide which moves to try first. Killer moves are moves that caused a
+// cutoff in a sibling position (i.e., at the same ply), and the history table rewards moves that
+// have caused cutoffs anywhere in the tree, weighted by how much work the cutoff saved.
+#[derive(Default, Debug, Clone)]
+struct MoveOrdering {
+    killers: Vec<[Option<Point>; 2]>,
+    history: HashMap<Point, u64>,
+}
+
+// The tactical class of a move, best first. Deriving `Ord` orders the variants as written.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Tactic {
+    /// Under misère rules, a move that completes a line of the player's own pieces
+    Losing,
+    Quiet,
+    Block,
+    Win,
+}
+
+/// What `alpha_beta()` returns when the true value of a position lies outside the `alpha`..`beta`
+/// window it was given.
+///
+/// Whichever is used, a result strictly inside the window is exact, and the search of the root
+/// position with the full `MoveScoreTurns::MIN`..`MoveScoreTurns::MAX` window is always exact.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundSemantics {
+    /// The result is clamped to the window: `alpha` when every move is worse than `alpha`, and
+    /// `beta` when a move at least as good as `beta` is found.
+    FailHard,
+    /// The best value actually found is returned, even when it lies outside the window. It is
+    /// then an upper bound (when at most `alpha`) or a lower bound (when at least `beta`) on the
+    /// true value, and is never looser than the fail-hard result.
+    #[default]
+    FailSoft,
+}
+
+/// The state carried through a single `alpha_beta()` search: the statistics being collected, the
+/// principal variation found so far and, when enabled, the move ordering heuristics.
+///
+/// Alpha-beta pruning only cuts off work when a good move is searched before the others. With
+/// move ordering turned on, the moves of every position are tried in the following order:
+///
+///  1. Moves that win immediately
+///  2. Moves that block an immediate win of the opponent
+///  3. Killer moves, then moves with a good history score
+///  4. Squares that lie on the most lines (the centre, then the corners, then the edges)
+///
+/// Without move ordering the moves are searched in `Point` order.
+#[derive(Default, Debug, Clone)]
+pub struct SearchContext {
+    pub stats: SearchStats,
+    pub bound_semantics: BoundSemantics,
+    ordering: Option<MoveOrdering>,
+    root_depth: Option<u32>,
+    // A triangular table: entry `ply` holds the best line found from the position currently
+    // being searched at that ply
+    principal_variations: Vec<Vec<(Point, SquareType)>>,
+    tree: Option<SearchTree>,
+}
+
+impl SearchContext {
+    pub fn new(move_ordering: bool) -> Self {
+        Self {
+            stats: SearchStats::default(),
+            bound_semantics: BoundSemantics::default(),
+            ordering: move_ordering.then(MoveOrdering::default),
+            root_depth: None,
+            principal_variations: Vec::new(),
+            tree: None,
+        }
+    }
+
+    pub fn with_bound_semantics(self, bound_semantics: BoundSemantics) -> Self {
+        Self {
+            bound_semantics,
+            ..self
+        }
+    }
+
+    /// Record every position searched (and pruned) in a `SearchTree`. Only the sequential
+    /// `alpha_beta()` records the tree, so this has no effect on `alpha_beta_parallel()` with more
+    /// than one thread.
+    pub fn with_search_tree(self) -> Self {
+        Self {
+            tree: Some(SearchTree::new()),
+            ..self
+        }
+    }
+
+    /// The tree recorded so far, when `with_search_tree()` was used
+    pub fn take_search_tree(&mut self) -> Option<SearchTree> {
+        self.tree.take()
+    }
+
+    /// A fresh context with the same settings and root, for searching in another thread
+    pub fn fork(&self) -> Self {
+        Self {
+            root_depth: self.root_depth,
+            ..Self::new(self.ordering.is_some()).with_bound_semantics(self.bound_semantics)
+        }
+    }
+
+    /// Called for every position the search visits, with `depth` plies left to search. The first
+    /// position visited is taken to be the root of the search.
+    pub fn visit(&mut self, depth: u32) {
+        let root_depth = *self.root_depth.get_or_insert(depth);
+        let ply = (root_depth - depth) as usize;
+        self.stats.nodes += 1;
+        self.stats.max_depth = self.stats.max_depth.max(root_depth - depth);
+
+        if self.principal_variations.len() < ply + 2 {
+            self.principal_variations.resize(ply + 2, Vec::new());
+        }
+        self.principal_variations[ply].clear();
+    }
+
+    /// Called when placing `piece` on `point` becomes the best move found so far in the position
+    /// being searched with `depth` plies left. The line below it is taken from the position the
+    /// move led to.
+    pub fn update_principal_variation(&mut self, depth: u32, point: Point, piece: SquareType) {
+        let ply = (self.root_depth.unwrap_or(depth) - depth) as usize;
+        let (line, below) = self.principal_variations.split_at_mut(ply + 1);
+        line[ply].clear();
+        line[ply].push((point, piece));
+        line[ply].extend_from_slice(&below[0]);
+    }
+
+    /// The best line found from the position searched at `ply` (`0` being the root of the search)
+    pub fn principal_variation(&self, ply: usize) -> &[(Point, SquareType)] {
+        self.principal_variations
+            .get(ply)
+            .map_or(&[], |line| line.as_slice())
+    }
+
+    /// The legal moves of `board` in the order `player` should search them
+    pub fn order_moves(&self, board: &Board, player: &Player) -> Vec<Point> {
+        let mut moves = board.legal_moves();
+        let Some(ordering) = &self.ordering else {
+            return moves;
+        };
+
+        let killers = ordering
+            .killers
+            .get(board.squares_filled())
+            .copied()
+            .unwrap_or_default();
+        moves.sort_by_cached_key(|point| {
+            // Under wild rules completing a line of either piece ends the game in the mover's favour
+            let completes_line = if board.rules().wild {
+                board.completes_line(point, &SquareType::X)
+                    || board.completes_line(point, &SquareType::O)
+            } else {
+                board.completes_line(point, &player.square_type())
+            };
+            let tactic = if board.rules().misere {
+                if completes_line {
+                    Tactic::Losing
+                } else {
+                    Tactic::Quiet
+                }
+            } else if completes_line {
+                Tactic::Win
+            } else if board.completes_line(point, &player.other().square_type()) {
+                Tactic::Block
+            } else {
+                Tactic::Quiet
+            };
+            let killer = killers.contains(&Some(*point));
+            let history = ordering.history.get(point).copied().unwrap_or(0);
+            Reverse((tactic, killer, history, board.lines_through(point)))
+        });
+        moves
+    }
+
+    /// Called when the search reaches `board`, with `player` to move
+    pub fn open_position(&mut self, board: &Board, player: &Player) {
+        if let Some(tree) = &mut self.tree {
+            tree.open(board, player);
+        }
+    }
+
+    /// Called when the search of the most recently opened position returns `value`
+    pub fn close_position(&mut self, value: &MoveScoreTurns) {
+        if let Some(tree) = &mut self.tree {
+            tree.close(value);
+        }
+    }
+
+    /// Called when searching `point` in a position of `board` caused the rest of its moves to be
+    /// pruned, with `depth` plies left to search.
+    /// The moves (squares and pieces) of `player` that were not searched are `pruned`.
+    pub fn record_cutoff(
+        &mut self,
+        board: &Board,
+        player: &Player,
+        point: &Point,
+        pruned: &[(Point, SquareType)],
+        depth: u32,
+    ) {
+        self.stats.cutoffs += 1;
+        if let Some(tree) = &mut self.tree {
+            tree.prune(board, player, pruned);
+        }
+        let Some(ordering) = &mut self.ordering else {
+            return;
+        };
+
+        let ply = board.squares_filled();
+        if ordering.killers.len() <= ply {
+            ordering.killers.resize(ply + 1, [None, None]);
+        }
+        let killers = &mut ordering.killers[ply];
+        if killers[0] != Some(*point) {
+            killers[1] = killers[0];
+            killers[0] = Some(*point);
+        }
+
+        *ordering.history.entry(*point).or_insert(0) += u64::from(depth) * u64::from(depth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::MoveScoreTurns;
+
+    fn search(
+        board: &Board,
+        player: &Player,
+        move_ordering: bool,
+    ) -> (MoveScoreTurns, SearchStats) {
+        let mut context = SearchContext::new(move_ordering);
+        let result = board.alpha_beta(
+            player,
+            board.blank_squares_remaining(),
+            &MoveScoreTurns::worst_for(player),
+            &MoveScoreTurns::worst_for(&player.other()),
+            &mut context,
+        );
+        (result, context.stats)
+    }
+
+    #[test]
+    fn principal_variation_leads_to_the_searched_result() {
+        let positions = [
+            ("B B B | B B B | B B B", Player::X),
+            ("X B B | B O B | B B X", Player::O),
+            ("X O B | B X B | B B B", Player::O),
+            ("X X B | O O B | B B B", Player::X),
+        ];
+        for (position, player) in positions {
+            let board = Board::from_string(position);
+            for threads in [1, 3] {
+                let result = board.alpha_beta_parallel(
+                    &player,
+                    board.blank_squares_remaining(),
+                    threads,
+                    &mut SearchContext::new(true),
+                );
+                let line = &result.principal_variation;
+                assert_eq!(
+                    line.moves[0],
+                    (
+                        result.move_score_turns.player_move,
+                        result.move_score_turns.piece
+                    )
+                );
+
+                let mut replay = board.clone();
+                let mut mover = player;
+                for (point, piece) in &line.moves {
+                    replay.place(point, *piece, &mover).unwrap();
+                    mover = mover.other();
+                }
+                assert_eq!(
+                    replay.game_status(),
+                    result.move_score_turns.score,
+                    "{position}"
+                );
+                assert_eq!(
+                    replay.blank_squares_remaining(),
+                    result.move_score_turns.blank_squares_remaining,
+                    "{position}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn principal_variation_display() {
+        let line = PrincipalVariation {
+            player: Player::X,
+            moves: vec![
+                (Point { x: 1, y: 1 }, SquareType::X),
+                (Point { x: 0, y: 0 }, SquareType::O),
+                (Point { x: 2, y: 2 }, SquareType::X),
+            ],
+            score: Some(GameStatus::XWin),
+        };
+        assert_eq!(
+            line.to_string(),
+            "X (1, 1) → O (0, 0) → X (2, 2) ... XWin in 3"
+        );
+
+        // A wild move placing the other player's piece names the piece
+        let line = PrincipalVariation {
+            player: Player::O,
+            moves: vec![(Point { x: 0, y: 2 }, SquareType::X)],
+            score: Some(GameStatus::OWin),
+        };
+        assert_eq!(line.to_string(), "O (0, 2) X ... OWin in 1");
+    }
+
+    #[test]
+    fn winning_move_is_ordered_first() {
+        let board = Board::from_string(
+            "X X B |
+             O O B |
+             B B B",
+        );
+        let moves = SearchContext::new(true).order_moves(&board, &Player::X);
+        assert_eq!(moves[0], Point { x: 0, y: 2 });
+        assert_eq!(moves[1], Point { x: 1, y: 2 });
+    }
+
+    #[test]
+    fn block_is_ordered_before_centre() {
+        let board = Board::from_string(
+            "O B B |
+             B B B |
+             O B X",
+        );
+        let moves = SearchContext::new(true).order_moves(&board, &Player::X);
+        assert_eq!(moves[0], Point { x: 1, y: 0 });
+        assert_eq!(moves[1], Point { x: 1, y: 1 });
+    }
+
+    #[test]
+    fn unordered_moves_are_in_point_order() {
+        let board = Board::initialize_blank_board(3);
+        let moves = SearchContext::new(false).order_moves(&board, &Player::X);
+        assert_eq!(moves, board.blank_squares());
+    }
+
+    #[test]
+    fn move_ordering_searches_fewer_nodes() {
+        let positions = [
+            ("B B B | B B B | B B B", Player::X),
+            ("X B B | B B B | B B B", Player::O),
+            ("X B B | B O B | B B X", Player::O),
+            ("B B B | B X B | B B O", Player::X),
+        ];
+        for (position, player) in positions {
+            let board = Board::from_string(position);
+            let (unordered, unordered_stats) = search(&board, &player, false);
+            let (ordered, ordered_stats) = search(&board, &player, true);
+            assert_eq!(ordered.score, unordered.score, "{position}");
+            assert_eq!(
+                ordered.blank_squares_remaining, unordered.blank_squares_remaining,
+                "{position}"
+            );
+            assert!(
+                ordered_stats.nodes < unordered_stats.nodes,
+                "{position}: {ordered_stats} vs {unordered_stats}"
+            );
+        }
+    }
+}
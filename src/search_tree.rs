@@ -0,0 +1,265 @@
+use crate::board_info::{Player, Point, SquareType};
+use crate::scoring::{GameStatus, MoveScoreTurns};
+use crate::search::SearchContext;
+use crate::tic_tac_toe_board::Board;
+use std::fmt::Write;
+
+/// A position in a `SearchTree`
+#[derive(Debug, Clone)]
+pub struct TreeNode {
+    pub board: Board,
+    /// The player to move in `board`
+    pub player: Player,
+    /// The move that led to this position from its parent: the square played and the piece
+    /// placed there, which under wild rules needn't be the mover's own
+    pub player_move: Option<(Point, SquareType)>,
+    pub parent: Option<usize>,
+    /// The value the search gave the position. `None` for positions that were pruned.
+    pub value: Option<MoveScoreTurns>,
+    /// Whether alpha-beta pruning skipped this position
+    pub pruned: bool,
+}
+
+/// A record of the positions a search visited, for visualising how minimax and alpha-beta pruning
+/// work. `to_dot()` renders it for [Graphviz][1], e.g. with `dot -Tsvg tree.dot -o tree.svg`.
+///
+/// [1]: https://graphviz.org
+#[derive(Debug, Clone, Default)]
+pub struct SearchTree {
+    /// Every position in the order it was visited. The root is the first.
+    pub nodes: Vec<TreeNode>,
+    // The positions currently being searched, innermost last
+    open: Vec<usize>,
+}
+
+impl SearchTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The tree `Board::alpha_beta()` searches from `board` with `player` to move, looking
+    /// `depth` plies ahead. The moves it didn't need to search are included as pruned positions.
+    pub fn alpha_beta(board: &Board, player: &Player, depth: u32) -> Self {
+        let mut context = SearchContext::new(true).with_search_tree();
+        board.alpha_beta(
+            player,
+            depth,
+            &MoveScoreTurns::worst_for(player),
+            &MoveScoreTurns::worst_for(&player.other()),
+            &mut context,
+        );
+        context.take_search_tree().unwrap_or_default()
+    }
+
+    /// Every position reachable from `board` within `depth` plies, valued by plain minimax
+    pub fn game_tree(board: &Board, player: &Player, depth: u32) -> Self {
+        let mut tree = Self::new();
+        tree.minimax(board, player, depth);
+        tree
+    }
+
+    fn minimax(&mut self, board: &Board, player: &Player, depth: u32) -> MoveScoreTurns {
+        self.open(board, player);
+        let mut value = MoveScoreTurns {
+            score: board.game_status(),
+            blank_squares_remaining: board.blank_squares_remaining(),
+            ..Default::default()
+        };
+        if depth > 0 && board.game_status() == GameStatus::StillPlaying {
+            let mut best: Option<MoveScoreTurns> = None;
+            for point in board.legal_moves() {
+                for piece in board.playable_pieces(player) {
+                    let mut new_board = board.clone();
+                    let _ = new_board.place(&point, piece, player);
+                    let mut new_value = self.minimax(&new_board, &player.other(), depth - 1);
+                    new_value.player_move = point;
+                    new_value.piece = piece;
+                    if best.is_none_or(|best| {
+                        new_value.cmp_for(&best, player) == std::cmp::Ordering::Greater
+                    }) {
+                        best = Some(new_value);
+                    }
+                }
+            }
+            value = best.expect("a position that is still being played has a blank square");
+        }
+        self.close(&value);
+        value
+    }
+
+    /// Start recording a position the search has just reached. It becomes a child of the
+    /// position currently open.
+    pub(crate) fn open(&mut self, board: &Board, player: &Player) {
+        let parent = self.open.last().copied();
+        let player_move = parent.and_then(|parent| {
+            let before = &self.nodes[parent].board;
+            before
+                .blank_squares()
+                .into_iter()
+                .find(|point| board.square(point) != SquareType::B)
+                .map(|point| (point, board.square(&point)))
+        });
+        self.open.push(self.nodes.len());
+        self.nodes.push(TreeNode {
+            board: board.clone(),
+            player: *player,
+            player_move,
+            parent,
+            value: None,
+            pruned: false,
+        });
+    }
+
+    /// Finish recording the position currently open, which the search valued as `value`
+    pub(crate) fn close(&mut self, value: &MoveScoreTurns) {
+        if let Some(index) = self.open.pop() {
+            self.nodes[index].value = Some(*value);
+        }
+    }
+
+    /// Record that `moves` of `player` in the open position `board` were pruned
+    pub(crate) fn prune(&mut self, board: &Board, player: &Player, moves: &[(Point, SquareType)]) {
+        let parent = self.open.last().copied();
+        for (point, piece) in moves {
+            let mut new_board = board.clone();
+            let _ = new_board.place(point, *piece, player);
+            self.nodes.push(TreeNode {
+                board: new_board,
+                player: player.other(),
+                player_move: Some((*point, *piece)),
+                parent,
+                value: None,
+                pruned: true,
+            });
+        }
+    }
+
+    /// The tree in the Graphviz DOT language. Each position is drawn as a small board labelled
+    /// with its value and each edge is labelled with the move played (and under wild rules the
+    /// piece placed). The best move of every searched position is drawn in bold, and pruned
+    /// positions are drawn dashed and grey.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph search_tree {\n");
+        dot.push_str("    node [shape=box, fontname=\"Courier\"];\n");
+        let mut has_children = vec![false; self.nodes.len()];
+        for parent in self.nodes.iter().filter_map(|node| node.parent) {
+            has_children[parent] = true;
+        }
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let mut label = board_label(&node.board);
+            match &node.value {
+                Some(value) if has_children[index] => write!(label, "\\n{value}").unwrap(),
+                Some(value) => write!(label, "\\n{}", value.score).unwrap(),
+                None => label.push_str("\\npruned"),
+            }
+            let style = if node.pruned {
+                ", style=dashed, color=gray, fontcolor=gray"
+            } else {
+                ""
+            };
+            writeln!(dot, "    n{index} [label=\"{label}\"{style}];").unwrap();
+
+            if let (Some(parent), Some((point, piece))) = (node.parent, node.player_move) {
+                let mover = self.nodes[parent].player;
+                // Under wild rules the same square is played with either piece, so the best
+                // move is only the edge that matches both
+                let best = self.nodes[parent]
+                    .value
+                    .is_some_and(|value| (value.player_move, value.piece) == (point, piece));
+                let mut label = format!("{mover} {point}");
+                if self.nodes[parent].board.rules().wild {
+                    write!(label, " {piece}").unwrap();
+                }
+                let style = match (node.pruned, best) {
+                    (true, _) => ", style=dashed, color=gray, fontcolor=gray",
+                    (false, true) => ", penwidth=3",
+                    (false, false) => "",
+                };
+                writeln!(dot, "    n{parent} -> n{index} [label=\"{label}\"{style}];").unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// The board as rows of squares separated by bars, e.g. "X|O| \nX| | \n |O| "
+fn board_label(board: &Board) -> String {
+    (0..board.size())
+        .map(|x| {
+            (0..board.size())
+                .map(|y| board.square(&Point { x, y }).to_string())
+                .collect::<Vec<String>>()
+                .join("|")
+        })
+        .collect::<Vec<String>>()
+        .join("\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_info::Rules;
+
+    #[test]
+    fn search_tree_matches_search_statistics() {
+        let board = Board::from_string("X O B | B X B | B B O");
+        let mut context = SearchContext::new(true).with_search_tree();
+        let result = board.alpha_beta(
+            &Player::X,
+            board.blank_squares_remaining(),
+            &MoveScoreTurns::worst_for(&Player::X),
+            &MoveScoreTurns::worst_for(&Player::O),
+            &mut context,
+        );
+        let tree = context.take_search_tree().unwrap();
+
+        let searched = tree.nodes.iter().filter(|node| !node.pruned).count();
+        assert_eq!(searched as u64, context.stats.nodes);
+        assert_eq!(tree.nodes[0].value, Some(result));
+        assert!(tree.nodes.iter().any(|node| node.pruned));
+        assert!(tree
+            .nodes
+            .iter()
+            .all(|node| node.pruned != node.value.is_some()));
+    }
+
+    #[test]
+    fn game_tree_has_every_position_up_to_the_depth() {
+        let board = Board::initialize_blank_board(3);
+        let tree = SearchTree::game_tree(&board, &Player::X, 2);
+        assert_eq!(tree.nodes.len(), 1 + 9 + 9 * 8);
+        assert!(tree.nodes.iter().all(|node| !node.pruned));
+        assert_eq!(
+            tree.nodes[1].player_move,
+            Some((Point { x: 0, y: 0 }, SquareType::X))
+        );
+    }
+
+    #[test]
+    fn dot_marks_pruned_branches() {
+        let board = Board::from_string("X X B | O O B | B B B");
+        let dot = SearchTree::alpha_beta(&board, &Player::X, 5).to_dot();
+        assert!(dot.starts_with("digraph search_tree {"));
+        assert!(dot.contains("n0 [label=\"X|X| \\nO|O| \\n | | \\n(0, 2):XWin\"];"));
+        assert!(dot.contains("n0 -> n1 [label=\"X (0, 2)\", penwidth=3];"));
+        assert!(dot.contains("style=dashed"));
+    }
+
+    #[test]
+    fn dot_bolds_only_the_best_piece_of_a_wild_move() {
+        let rules = Rules {
+            wild: true,
+            ..Rules::default()
+        };
+        let board = Board::from_position("XXB/OBB/OBB", rules).unwrap();
+        let dot = SearchTree::game_tree(&board, &Player::O, 1).to_dot();
+        let bold: Vec<&str> = dot
+            .lines()
+            .filter(|line| line.contains("penwidth"))
+            .collect();
+        assert_eq!(bold.len(), 1, "{dot}");
+        assert!(bold[0].contains("O (0, 2) X"), "{dot}");
+    }
+}
@@ -0,0 +1,235 @@
+use crate::board_info::{Point, Rules, SquareType};
+use crate::tic_tac_toe_board::Board;
+
+/// One of the eight ways a square board can be rotated or reflected onto itself. Positions that
+/// are the same up to one of these play out the same way, so they can share book entries and
+/// statistics. See `Transform` for the symmetries of boards with other rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    Identity,
+    /// A quarter turn clockwise
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Swap the top and bottom rows
+    FlipRows,
+    /// Swap the left and right columns
+    FlipCols,
+    /// Reflect along the main (top left to bottom right) diagonal
+    Transpose,
+    /// Reflect along the other diagonal
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipRows,
+        Symmetry::FlipCols,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    /// Where `point` ends up after transforming a board of width `size`
+    pub fn apply(&self, point: &Point, size: usize) -> Point {
+        let (x, y) = (point.x, point.y);
+        let last = size - 1;
+        let (x, y) = match self {
+            Symmetry::Identity => (x, y),
+            Symmetry::Rotate90 => (y, last - x),
+            Symmetry::Rotate180 => (last - x, last - y),
+            Symmetry::Rotate270 => (last - y, x),
+            Symmetry::FlipRows => (last - x, y),
+            Symmetry::FlipCols => (x, last - y),
+            Symmetry::Transpose => (y, x),
+            Symmetry::AntiTranspose => (last - y, last - x),
+        };
+        Point { x, y }
+    }
+
+    /// The symmetry that undoes this one
+    pub fn inverse(&self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => *other,
+        }
+    }
+}
+
+/// A `Symmetry` followed by shifting every square `shift` rows down and columns right, wrapping
+/// around the edges. Shifting only keeps positions the same on a torus, where the lines wrap
+/// around too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transform {
+    pub symmetry: Symmetry,
+    pub shift: Point,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        symmetry: Symmetry::Identity,
+        shift: Point { x: 0, y: 0 },
+    };
+
+    /// Every transform that doesn't change how a game played with `rules` on a board of width
+    /// `size` plays out. Under gravity the bottom of the board has to stay at the bottom, which
+    /// only leaves swapping the columns and, on a torus, shifting them.
+    pub fn for_rules(rules: &Rules, size: usize) -> Vec<Transform> {
+        let symmetries: &[Symmetry] = if rules.gravity {
+            &[Symmetry::Identity, Symmetry::FlipCols]
+        } else {
+            &Symmetry::ALL
+        };
+        let row_shifts = if rules.torus && !rules.gravity {
+            size
+        } else {
+            1
+        };
+        let col_shifts = if rules.torus { size } else { 1 };
+        symmetries
+            .iter()
+            .flat_map(|symmetry| {
+                (0..row_shifts).flat_map(move |x| {
+                    (0..col_shifts).map(move |y| Transform {
+                        symmetry: *symmetry,
+                        shift: Point { x, y },
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Where `point` ends up after transforming a board of width `size`
+    pub fn apply(&self, point: &Point, size: usize) -> Point {
+        let point = self.symmetry.apply(point, size);
+        Point {
+            x: (point.x + self.shift.x) % size,
+            y: (point.y + self.shift.y) % size,
+        }
+    }
+
+    /// Where `point` was before transforming a board of width `size`
+    pub fn unapply(&self, point: &Point, size: usize) -> Point {
+        let point = Point {
+            x: (point.x + size - self.shift.x) % size,
+            y: (point.y + size - self.shift.y) % size,
+        };
+        self.symmetry.inverse().apply(&point, size)
+    }
+}
+
+/// The position of `board` after transforming it, in the format of `Board::position()`
+pub fn transformed_position(board: &Board, transform: &Transform) -> String {
+    let size = board.size();
+    let rows: Vec<String> = (0..size)
+        .map(|x| {
+            (0..size)
+                .map(|y| square_letter(board.square(&transform.unapply(&Point { x, y }, size))))
+                .collect()
+        })
+        .collect();
+    rows.join("/")
+}
+
+fn square_letter(square_type: SquareType) -> char {
+    match square_type {
+        SquareType::B => 'B',
+        SquareType::O => 'O',
+        SquareType::X => 'X',
+        SquareType::Blocked => '#',
+    }
+}
+
+/// The representative of every position `board` can be transformed into by the transforms of its
+/// rules: the one whose position string sorts first. Also returns the transform that takes `board`
+/// to it.
+pub fn canonical_position(board: &Board) -> (String, Transform) {
+    Transform::for_rules(&board.rules(), board.size())
+        .into_iter()
+        .map(|transform| (transformed_position(board, &transform), transform))
+        .min_by(|a, b| a.0.cmp(&b.0))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse_undoes_every_symmetry() {
+        for symmetry in Symmetry::ALL {
+            for x in 0..4 {
+                for y in 0..4 {
+                    let point = Point { x, y };
+                    let moved = symmetry.apply(&point, 4);
+                    assert_eq!(symmetry.inverse().apply(&moved, 4), point, "{symmetry:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn symmetric_positions_share_a_canonical_position() {
+        let corners = [
+            "X O B | B B B | B B B",
+            "B B X | B B O | B B B",
+            "X B B | O B B | B B B",
+        ];
+        let canonical: Vec<String> = corners
+            .iter()
+            .map(|position| canonical_position(&Board::from_string(position)).0)
+            .collect();
+        assert!(canonical.iter().all(|position| *position == canonical[0]));
+
+        let centre = canonical_position(&Board::from_string("B B B | B X B | B B B")).0;
+        assert_ne!(centre, canonical[0]);
+        assert_eq!(centre, "BBB/BXB/BBB");
+    }
+
+    #[test]
+    fn gravity_positions_are_only_mirrored() {
+        let rules = Rules {
+            gravity: true,
+            ..Default::default()
+        };
+        let left = Board::from_position("BBB/BBB/XBB", rules).unwrap();
+        let right = Board::from_position("BBB/BBB/BBX", rules).unwrap();
+        assert_eq!(canonical_position(&left).0, canonical_position(&right).0);
+
+        // Turned on its side the piece would be floating, so that isn't the same position
+        let (position, _) = canonical_position(&left);
+        assert_eq!(position, "BBB/BBB/BBX");
+    }
+
+    #[test]
+    fn canonical_transform_maps_the_board_onto_the_canonical_position() {
+        let board = Board::from_string("B B B | B B O | X B B");
+        let (position, transform) = canonical_position(&board);
+        assert_eq!(transformed_position(&board, &transform), position);
+    }
+
+    #[test]
+    fn torus_positions_are_the_same_when_shifted() {
+        let rules = Rules {
+            torus: true,
+            ..Default::default()
+        };
+        assert_eq!(Transform::for_rules(&rules, 4).len(), 8 * 16);
+        let board = Board::from_position("XOBB/BBBB/BBBB/BBBB", rules).unwrap();
+        let shifted = Board::from_position("BBBB/BBBB/BBBB/BBXO", rules).unwrap();
+        assert_eq!(canonical_position(&board).0, canonical_position(&shifted).0);
+
+        let (position, transform) = canonical_position(&shifted);
+        assert_eq!(transformed_position(&shifted, &transform), position);
+        for x in 0..4 {
+            for y in 0..4 {
+                let point = Point { x, y };
+                assert_eq!(transform.unapply(&transform.apply(&point, 4), 4), point);
+            }
+        }
+    }
+}
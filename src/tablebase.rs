@@ -0,0 +1,403 @@
+use crate::board_info::{Player, Point, Rules, SquareType};
+use crate::engine::{ComputerPlayer, SearchReport};
+use crate::game_variant::{GameVariant, Placement, Position, Report, VariantEngine, WIN};
+use crate::scoring::{GameStatus, MoveScoreTurns};
+use crate::search::{SearchResult, SearchStats};
+use crate::tic_tac_toe_board::Board;
+use std::time::Instant;
+
+/// A solved-game table for 3x3 tic-tac-toe: the perfect-play result of every position that can be
+/// reached from the empty board, whichever player moves first.
+///
+/// Positions are indexed by reading the squares row by row as the digits of a base 3 number
+/// (`B` = 0, `O` = 1, `X` = 2), doubled to make room for the player to move. Each position takes a
+/// single byte: the upper bits hold the result (0 for positions that can't be reached, then
+/// `OWin`, `Draw`, and `XWin`) and the lower four bits hold the number of plies until the game
+/// ends with best play. That makes the whole table 39,366 bytes, and every lookup O(1).
+///
+/// The table is embedded in the program. It can be regenerated with
+/// `tic-tac-toe generate-tablebase src/tablebase_3x3.bin`.
+pub struct Tablebase {
+    entries: Vec<u8>,
+}
+
+impl Tablebase {
+    /// The width of the only board the tablebase covers
+    pub const SIZE: usize = 3;
+    const ENTRIES: usize = 2 * 3usize.pow((Self::SIZE * Self::SIZE) as u32);
+    const EMBEDDED: &'static [u8] = include_bytes!("tablebase_3x3.bin");
+
+    /// The tablebase that was generated ahead of time and built into the program
+    pub fn embedded() -> Self {
+        Self::from_bytes(Self::EMBEDDED.to_vec()).expect("the embedded tablebase is well formed")
+    }
+
+    pub fn from_bytes(entries: Vec<u8>) -> Result<Self, String> {
+        if entries.len() == Self::ENTRIES {
+            Ok(Self { entries })
+        } else {
+            Err(format!(
+                "A tablebase has {} entries. Got {}",
+                Self::ENTRIES,
+                entries.len()
+            ))
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.entries
+    }
+
+    /// Solve every position reachable from `Board::initialize_blank_board()`
+    pub fn generate() -> Self {
+        let mut tablebase = Self {
+            entries: vec![0; Self::ENTRIES],
+        };
+        let empty_board = Board::initialize_blank_board(Self::SIZE);
+        for player in [Player::X, Player::O] {
+            tablebase.solve(&empty_board, &player);
+        }
+        tablebase
+    }
+
+    /// Generate the tablebase and write it to `path`
+    pub fn generate_to_file(path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, Self::generate().as_bytes())
+    }
+
+    /// The number of positions in the tablebase
+    pub fn len(&self) -> usize {
+        self.entries.iter().filter(|&&entry| entry != 0).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The perfect-play result of the position with `player` to move. `None` when the position
+    /// is not a standard 3x3 game or cannot be reached in a real game.
+    pub fn lookup(&self, board: &Board, player: &Player) -> Option<MoveScoreTurns> {
+        let entry = self.entries[Self::index(board, player)?];
+        let score = match entry >> 4 {
+            1 => GameStatus::OWin,
+            2 => GameStatus::Draw,
+            3 => GameStatus::XWin,
+            _ => return None,
+        };
+        Some(MoveScoreTurns {
+            score,
+            blank_squares_remaining: board.blank_squares_remaining() - u32::from(entry & 0x0F),
+            ..Default::default()
+        })
+    }
+
+    /// The best move for `player` and the line of perfect play following it, found by looking up
+    /// every position the line passes through. `None` when the game is already over or the
+    /// position isn't in the tablebase.
+    pub fn best_move(&self, board: &Board, player: &Player) -> Option<SearchResult> {
+        let move_score_turns = self.best_reply(board, player)?;
+        let mut line = Vec::new();
+        let mut position = board.clone();
+        let mut mover = *player;
+        let mut next = Some(move_score_turns);
+        while let Some(reply) = next {
+            line.push((reply.player_move, reply.piece));
+            let _ = position.insert(&reply.player_move, mover.square_type());
+            mover = mover.other();
+            next = self.best_reply(&position, &mover);
+        }
+        Some(SearchResult::new(*player, move_score_turns, &line))
+    }
+
+    // Of the moves available to `player`, the first one with the best tablebase result
+    fn best_reply(&self, board: &Board, player: &Player) -> Option<MoveScoreTurns> {
+        if board.game_status() != GameStatus::StillPlaying {
+            return None;
+        }
+        let mut best: Option<MoveScoreTurns> = None;
+        for point in board.blank_squares() {
+            let mut new_board = board.clone();
+            let _ = new_board.insert(&point, player.square_type());
+            let mut value = self.lookup(&new_board, &player.other())?;
+            value.player_move = point;
+            value.piece = player.square_type();
+            if best.is_none_or(|best| value.cmp_for(&best, player) == std::cmp::Ordering::Greater) {
+                best = Some(value);
+            }
+        }
+        best
+    }
+
+    // A plain memoized minmax search that fills in the table as it goes
+    fn solve(&mut self, board: &Board, player: &Player) -> MoveScoreTurns {
+        if let Some(value) = self.lookup(board, player) {
+            return value;
+        }
+
+        let value = if board.game_status() == GameStatus::StillPlaying {
+            board
+                .blank_squares()
+                .iter()
+                .map(|point| {
+                    let mut new_board = board.clone();
+                    let _ = new_board.insert(point, player.square_type());
+                    self.solve(&new_board, &player.other())
+                })
+                .max_by(|a, b| a.cmp_for(b, player))
+                .unwrap()
+        } else {
+            MoveScoreTurns {
+                score: board.game_status(),
+                blank_squares_remaining: board.blank_squares_remaining(),
+                ..Default::default()
+            }
+        };
+
+        let result: u8 = match value.score {
+            GameStatus::OWin => 1,
+            GameStatus::Draw => 2,
+            GameStatus::XWin => 3,
+            GameStatus::StillPlaying => unreachable!("every game ends on a 3x3 board"),
+        };
+        let plies = board.blank_squares_remaining() - value.blank_squares_remaining;
+        let index = Self::index(board, player).unwrap();
+        self.entries[index] = result << 4 | plies as u8;
+        value
+    }
+
+    fn index(board: &Board, player: &Player) -> Option<usize> {
+        if board.size() != Self::SIZE || board.rules() != Rules::default() {
+            return None;
+        }
+        let mut index = 0;
+        for x in 0..Self::SIZE {
+            for y in 0..Self::SIZE {
+                index = index * 3
+                    + match board.square(&Point { x, y }) {
+                        SquareType::B => 0,
+                        SquareType::O => 1,
+                        SquareType::X => 2,
+                        // The tablebase only covers the empty board's game tree
+                        SquareType::Blocked => return None,
+                    };
+            }
+        }
+        Some(index * 2 + usize::from(*player == Player::O))
+    }
+}
+
+/// A computer opponent that plays perfectly and instantly by looking its moves up in the
+/// `Tablebase`
+pub struct TablebasePlayer {
+    tablebase: Tablebase,
+}
+
+impl TablebasePlayer {
+    pub fn new() -> Self {
+        Self {
+            tablebase: Tablebase::embedded(),
+        }
+    }
+}
+
+impl Default for TablebasePlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ComputerPlayer for TablebasePlayer {
+    fn choose_move(&mut self, board: &Board, player: &Player) -> SearchReport {
+        let start = Instant::now();
+        let result = self
+            .tablebase
+            .best_move(board, player)
+            .expect("every position of a 3x3 game is in the tablebase");
+        SearchReport {
+            player_move: result.move_score_turns.player_move,
+            piece: result.move_score_turns.piece,
+            move_score_turns: Some(result.move_score_turns),
+            stats: SearchStats {
+                nodes: board.blank_squares_remaining().into(),
+                cutoffs: 0,
+                max_depth: 1,
+            },
+            elapsed: start.elapsed(),
+            principal_variation: result.principal_variation,
+        }
+    }
+}
+
+/// Chooses between the legal moves of the `Position`, so a handicap that rules some moves out,
+/// like the corner handicap, is kept to
+impl VariantEngine<Position> for TablebasePlayer {
+    fn choose_move(&mut self, game: &Position) -> Report<Placement> {
+        let start = Instant::now();
+        let player = game.to_move();
+        let mut best: Option<(Placement, MoveScoreTurns)> = None;
+        for placement in game.legal_moves() {
+            let mut board = game.board().clone();
+            let _ = board.place(&placement.point, placement.piece, &player);
+            let value = self
+                .tablebase
+                .lookup(&board, &player.other())
+                .expect("every position of a 3x3 game is in the tablebase");
+            if best.is_none_or(|(_, best)| {
+                value.cmp_for(&best, &player) == std::cmp::Ordering::Greater
+            }) {
+                best = Some((placement, value));
+            }
+        }
+        let (player_move, value) = best.expect("a game that isn't over has a legal move");
+
+        // The rest of the line is the tablebase's best play for both sides
+        let mut board = game.board().clone();
+        let _ = board.place(&player_move.point, player_move.piece, &player);
+        let mut line = vec![player_move];
+        if let Some(result) = self.tablebase.best_move(&board, &player.other()) {
+            line.extend(
+                result
+                    .principal_variation
+                    .moves
+                    .into_iter()
+                    .map(|(point, piece)| Placement { point, piece }),
+            );
+        }
+        // Wins found sooner and losses put off longer score further from 0, as in `Negamax`
+        let plies = line.len() as i32;
+        let score = match (value.score, player) {
+            (GameStatus::XWin, Player::X) | (GameStatus::OWin, Player::O) => WIN - plies,
+            (GameStatus::XWin, Player::O) | (GameStatus::OWin, Player::X) => plies - WIN,
+            (GameStatus::Draw | GameStatus::StillPlaying, _) => 0,
+        };
+        Report {
+            player_move,
+            score: Some(score),
+            line,
+            nodes: board.blank_squares_remaining().into(),
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Handicap;
+    use crate::game_variant::Negamax;
+    use crate::search::SearchContext;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn embedded_tablebase_is_up_to_date() {
+        assert!(Tablebase::embedded().as_bytes() == Tablebase::generate().as_bytes());
+    }
+
+    #[test]
+    fn tablebase_has_every_reachable_position() {
+        let tablebase = Tablebase::embedded();
+        // The well known count of positions in games where X moves first
+        let x_first = (0..Tablebase::ENTRIES)
+            .filter(|index| tablebase.entries[*index] != 0)
+            .filter(|index| {
+                let mut code = index / 2;
+                let (mut xs, mut os) = (0, 0);
+                while code > 0 {
+                    match code % 3 {
+                        1 => os += 1,
+                        2 => xs += 1,
+                        _ => (),
+                    }
+                    code /= 3;
+                }
+                let o_to_move = index % 2 == 1;
+                (xs == os && !o_to_move) || (xs == os + 1 && o_to_move)
+            })
+            .count();
+        assert_eq!(x_first, 5_478);
+    }
+
+    #[test]
+    fn tablebase_matches_alpha_beta() {
+        let tablebase = Tablebase::embedded();
+        let empty_board = Board::initialize_blank_board(3);
+        let mut stack = vec![(empty_board.clone(), Player::X), (empty_board, Player::O)];
+        let mut checked = std::collections::HashSet::new();
+        while let Some((board, player)) = stack.pop() {
+            if board.game_status() != GameStatus::StillPlaying
+                || !checked.insert(Tablebase::index(&board, &player))
+            {
+                continue;
+            }
+
+            let searched = board.alpha_beta(
+                &player,
+                board.blank_squares_remaining(),
+                &MoveScoreTurns::worst_for(&player),
+                &MoveScoreTurns::worst_for(&player.other()),
+                &mut SearchContext::new(true),
+            );
+            let looked_up = tablebase.best_move(&board, &player).unwrap();
+            assert_eq!(
+                looked_up.move_score_turns.cmp(&searched),
+                Ordering::Equal,
+                "\n{board}"
+            );
+            assert_eq!(
+                looked_up.move_score_turns.blank_squares_remaining,
+                searched.blank_squares_remaining
+            );
+            let stored = tablebase.lookup(&board, &player).unwrap();
+            assert_eq!(stored.cmp(&searched), Ordering::Equal);
+
+            for point in board.blank_squares() {
+                let mut new_board = board.clone();
+                new_board.insert(&point, player.square_type()).unwrap();
+                stack.push((new_board, player.other()));
+            }
+        }
+    }
+
+    #[test]
+    fn unreachable_positions_are_not_in_the_tablebase() {
+        let board = Board::from_string(
+            "X X X |
+             X B B |
+             B B B",
+        );
+        assert_eq!(Tablebase::embedded().lookup(&board, &Player::O), None);
+        assert_eq!(
+            Tablebase::embedded().lookup(&Board::initialize_blank_board(4), &Player::X),
+            None
+        );
+    }
+
+    #[test]
+    fn tablebase_scores_positions_like_negamax() {
+        let mut player = TablebasePlayer::new();
+        for (position, to_move) in [
+            ("BBB/BBB/BBB", Player::X),
+            ("XBB/BOB/BBB", Player::X),
+            ("XXB/OOB/BBB", Player::O),
+            ("XOB/BXB/BBB", Player::O),
+        ] {
+            let game = Position::new(position.parse().unwrap(), to_move);
+            let report = VariantEngine::choose_move(&mut player, &game);
+            let searched = Negamax::new(9).choose_move(&game);
+            assert_eq!(report.score, searched.score, "{position}");
+            assert_eq!(report.line[0], report.player_move);
+        }
+
+        // Under the corner handicap the tablebase's first move is a corner
+        let game = Position::with_handicaps(
+            Board::initialize_blank_board(3),
+            Player::X,
+            Player::O,
+            &[Handicap::Corner],
+        );
+        let Point { x, y } = VariantEngine::choose_move(&mut player, &game)
+            .player_move
+            .point;
+        assert!([0, 2].contains(&x) && [0, 2].contains(&y));
+    }
+}
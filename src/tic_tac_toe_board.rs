@@ -1,41 +1,13 @@
-use crate::board_info::{Opponent, Player, Point, SquareType};
-use crate::config::{Config, MoveFirst, PlayerIsX};
+use crate::board_info::{Player, Point, Rules, SquareType};
+use crate::config::Config;
+use crate::engine;
+use crate::game_variant::{self, Position};
 use crate::scoring::{GameStatus, MoveScoreTurns, PartialLineStatus};
+use crate::search::{BoundSemantics, SearchContext, SearchResult, SearchStats};
+use crate::symmetry::{transformed_position, Transform};
 use std::collections::HashSet;
-use std::io;
-
-// Contains the errors that can occur when playing the game
-#[derive(Debug)]
-enum BoardErr {
-    Io(std::io::Error),
-    Parse(std::num::ParseIntError),
-    NumInputArgs(String),
-    Move(String),
-}
-
-impl std::fmt::Display for BoardErr {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            BoardErr::Io(e) => write!(f, "{e}"),
-            BoardErr::Parse(e) => write!(f, "{e}"),
-            BoardErr::NumInputArgs(e) | BoardErr::Move(e) => write!(f, "{e}"),
-        }
-    }
-}
-
-impl From<std::io::Error> for BoardErr {
-    fn from(value: std::io::Error) -> Self {
-        Self::Io(value)
-    }
-}
-
-impl From<std::num::ParseIntError> for BoardErr {
-    fn from(value: std::num::ParseIntError) -> Self {
-        Self::Parse(value)
-    }
-}
-
-impl std::error::Error for BoardErr {}
+use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use std::sync::Mutex;
 
 /// A newtype wrapper to allow for custom `Display` of `Board.blank_squares_set`
 #[derive(Clone, Debug)]
@@ -62,12 +34,13 @@ impl From<HashSet<Point>> for PointCollection {
 
 /// This is the core of the entire program and contains all the data and functions needed to play a
 /// game of tic-tac-toe.
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Board {
     content: Vec<Vec<SquareType>>,
     size: usize,
     blank_squares_set: PointCollection,
     game_status: GameStatus,
+    rules: Rules,
 }
 
 // This creates the classic *hashtag* board
@@ -94,6 +67,16 @@ impl std::fmt::Display for Board {
     }
 }
 
+/// Parses a position written as `Board::position()` writes it: one letter per square (`B`, `O`, or
+/// `X`) with the rows separated by slashes, e.g. "XOB/BXB/BBO"
+impl std::str::FromStr for Board {
+    type Err = String;
+
+    fn from_str(position: &str) -> Result<Self, Self::Err> {
+        Board::from_position(position, Rules::default())
+    }
+}
+
 #[rustfmt::skip]
 impl Board {
     const INTRO_MESSAGE: &'static str =
@@ -102,6 +85,7 @@ impl Board {
          \n\
          Play by entering the coordinates of the square you want to play in.\n\
          Coordinates are entered as two numbers separated by a space (e.g., \"0 1\").\n\
+         Enter \"hint\" to see the computer's suggestion and the line of play it expects.\n\
          The square coordinates are as follows:\n\
          \n     0   1   2 \
          \n 0    |   |    \
@@ -112,32 +96,49 @@ impl Board {
          \n\
          ##################################################\n";
 
-    const OUTRO_MESSAGE: &'static str =
-        "\n##################################################\n\
-         \
-         \nThanks for playing!\
-         \n-------------------\n";
+    const MISERE_MESSAGE: &'static str =
+        "Misere rules: completing a line of your own pieces LOSES the game.\n\
+         Force the computer to complete a line before you do!\n\
+         \n\
+         ##################################################\n";
+
+    const WILD_MESSAGE: &'static str =
+        "Wild rules: on every turn you may place either an X or an O, and whoever\n\
+         completes a line of either wins. Add the piece after the coordinates\n\
+         (e.g., \"1 1 O\").\n\
+         \n\
+         ##################################################\n";
+
+    const GRAVITY_MESSAGE: &'static str =
+        "Gravity rules: pieces drop to the lowest blank square of their column, so\n\
+         enter just the column you want to play in (e.g., \"2\").\n\
+         \n\
+         ##################################################\n";
 }
 
 impl Board {
-    const BOARD_SIZE: usize = 3;
-    pub const EMPTY_BOARD_BLANK_SQUARES_REMAINING: u32 = 9;
-
     fn new(
         content: Vec<Vec<SquareType>>,
         size: usize,
         blank_squares_set: PointCollection,
         game_status: GameStatus,
+        rules: Rules,
     ) -> Self {
         Self {
             content,
             size,
             blank_squares_set,
             game_status,
+            rules,
         }
     }
 
-    fn initialize_blank_board(size: usize) -> Board {
+    pub fn initialize_blank_board(size: usize) -> Board {
+        Board::with_rules(size, Rules::default())
+    }
+
+    /// An empty board to be played with the given `rules`
+    pub fn with_rules(size: usize, rules: Rules) -> Board {
         let blank_array = vec![vec![SquareType::B; size]; size];
         let mut blank_squares_set: PointCollection = HashSet::new().into();
         for i in 0..size {
@@ -150,9 +151,96 @@ impl Board {
             size,
             blank_squares_set,
             GameStatus::StillPlaying,
+            rules,
         )
     }
 
+    /// A position written as `Board::position()` writes it, to be played with the given `rules`
+    pub fn from_position(position: &str, rules: Rules) -> Result<Board, String> {
+        let rows: Vec<&str> = position.trim().split('/').collect();
+        let size = rows.len();
+        let mut board = Board::with_rules(size, rules);
+        // The bottom row is filled first, so positions played under gravity can be rebuilt
+        for (x, row) in rows.iter().enumerate().rev() {
+            if row.chars().count() != size {
+                return Err(format!(
+                    "Every row of a {size}x{size} position needs {size} squares. Got {row:?}"
+                ));
+            }
+            for (y, square) in row.chars().enumerate() {
+                let square_type = match square {
+                    'B' | 'b' => continue,
+                    '#' => {
+                        board.block(&Point { x, y })?;
+                        continue;
+                    }
+                    'O' | 'o' => SquareType::O,
+                    'X' | 'x' => SquareType::X,
+                    c => return Err(format!("Not a matching square type: {c:?}")),
+                };
+                board.insert(&Point { x, y }, square_type)?;
+            }
+        }
+        Ok(board)
+    }
+
+    /// The number of squares along each side of the board
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn game_status(&self) -> GameStatus {
+        self.game_status
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// What has been played at `point`
+    pub fn square(&self, point: &Point) -> SquareType {
+        self.content[point.x][point.y]
+    }
+
+    /// The board written compactly (the format `Board::from_str()` reads), e.g. "XOB/BXB/BBO"
+    pub fn position(&self) -> String {
+        transformed_position(self, &Transform::IDENTITY)
+    }
+
+    /// The squares that can still be played in, in a stable (sorted) order
+    pub fn blank_squares(&self) -> Vec<Point> {
+        let mut blank_squares: Vec<Point> = self.blank_squares_set.0.iter().copied().collect();
+        blank_squares.sort();
+        blank_squares
+    }
+
+    /// The squares a piece can be played in next, in a stable (sorted) order. These are the blank
+    /// squares, except under gravity where only the lowest blank square of each column can be
+    /// played.
+    pub fn legal_moves(&self) -> Vec<Point> {
+        if self.rules.gravity {
+            (0..self.size)
+                .filter_map(|column| self.drop_point(column))
+                .collect()
+        } else {
+            self.blank_squares()
+        }
+    }
+
+    /// Where a piece played in `column` lands under gravity: the blank square resting on the
+    /// bottom of the column or on the highest piece or blocked square in it. `None` if the column
+    /// is full.
+    pub fn drop_point(&self, column: usize) -> Option<Point> {
+        (0..self.size)
+            .map(|x| Point { x, y: column })
+            .take_while(|point| self.content[point.x][point.y] == SquareType::B)
+            .last()
+    }
+
+    pub fn blank_squares_remaining(&self) -> u32 {
+        self.blank_squares_set.0.len() as u32
+    }
+
     /// This is the function to run the tic-tac-toe game.
     ///
     /// It consists of the player alternating turns with the ai opponent. The player moves by
@@ -162,177 +250,360 @@ impl Board {
     /// strings separated by a space, non-numeric input, and numeric input that is out of bounds or
     /// in an already played location. The game ends when either opponent wins or when the board is
     /// filled (i.e. a draw).
+    ///
+    /// The game is played as a `Position` by `game_variant::run()`, like every other variant, and
+    /// against the engine chosen by `engine::for_position()`.
     pub fn run(config: &Config) {
-        // Configure if the user or computer goes first
-        let mut current_player = match config.first_or_second {
-            MoveFirst(true) => Opponent::User,
-            MoveFirst(false) => Opponent::Computer,
-        };
-
-        // Configure if the user or computer is playing with the X (or O) pieces
-        let player_piece_type = match config.player_piece_type {
-            PlayerIsX(true) => Player::X,
-            PlayerIsX(false) => Player::O,
-        };
-
-        // Print intro message
-        println!("{}", Self::INTRO_MESSAGE);
-
-        let mut tic_tac_toe_board = Board::initialize_blank_board(Self::BOARD_SIZE);
-        println!("Initial board:");
-        while tic_tac_toe_board.game_status == GameStatus::StillPlaying {
-            // Print board
-            println!("\n{tic_tac_toe_board}\n");
-
-            match current_player {
-                Opponent::User => {
-                    println!("Your move: ");
-                    while let Err(e) = Board::try_move(&mut tic_tac_toe_board, &player_piece_type) {
-                        println! {"{e}"};
-                    }
-                }
-                Opponent::Computer => {
-                    // Calculate where the opponent should move
-                    let opponent_move = tic_tac_toe_board
-                        .alpha_beta(
-                            &player_piece_type.other(),
-                            9,
-                            &MoveScoreTurns::MIN,
-                            &MoveScoreTurns::MAX,
-                        )
-                        .player_move;
-
-                    println!("Opponent's move:\n{} {}", opponent_move.x, opponent_move.y);
-                    tic_tac_toe_board
-                        .insert(&opponent_move, player_piece_type.other().square_type())
-                        .expect("alpha_beta() should not choose an invalid insert position");
-                }
+        // Configure which algorithm the computer uses to choose its moves
+        let mut computer = engine::for_position(config);
+
+        let rules = config.rules();
+        let mut intro = Self::INTRO_MESSAGE.to_string();
+        for (applies, message) in [
+            (rules.misere, Self::MISERE_MESSAGE),
+            (rules.wild, Self::WILD_MESSAGE),
+            (rules.gravity, Self::GRAVITY_MESSAGE),
+        ] {
+            if applies {
+                intro = format!("{intro}\n{message}");
             }
-            current_player = current_player.other();
         }
 
-        // Print the final result of the game
-        println!("\nFinal Board: \n{tic_tac_toe_board}\n");
-        println!("Final Status: {:?}", tic_tac_toe_board.game_status);
-        println!("{}", Self::OUTRO_MESSAGE);
-    }
-
-    fn get_user_move() -> Result<Point, BoardErr> {
-        let mut player_move = String::new();
-        io::stdin().read_line(&mut player_move)?;
-        let user_input_as_usize = player_move
-            .split_whitespace()
-            .map(str::parse)
-            .collect::<Result<Vec<usize>, _>>()?;
-
-        if user_input_as_usize.len() == Point::NUM_ARGUMENTS {
-            Ok(Point {
-                x: user_input_as_usize[0],
-                y: user_input_as_usize[1],
-            })
-        } else {
-            Err(BoardErr::NumInputArgs(format!(
-                "Incorrect number of input arguments. Got {}, Expected {}",
-                user_input_as_usize.len(),
-                Point::NUM_ARGUMENTS
-            )))
-        }
-    }
-
-    fn try_move(board: &mut Board, player: &Player) -> Result<(), BoardErr> {
-        let user_move = Board::get_user_move()?;
-        board
-            .insert(&user_move, player.square_type())
-            .map_err(BoardErr::Move)?;
-        Ok(())
+        let starting_board = config.starting_board();
+        let new_game = |first, user| {
+            Position::with_handicaps(starting_board.clone(), first, user, &config.handicaps)
+        };
+        game_variant::run(config, &intro, new_game, computer.as_mut());
     }
 
-    // This is an implementation of a depth-limited minmax algorithm with alpha-beta pruning
-    // [Wikipedia][1] has a good explanation of the algorithm
-    //
-    // [1]: https://en.wikipedia.org/wiki/Alpha-beta_pruning
-    fn alpha_beta(
+    /// This is an implementation of a depth-limited minmax algorithm with alpha-beta pruning,
+    /// written in the negamax style: both players share the same code, and every comparison is
+    /// made from the point of view of the `player` to move using `MoveScoreTurns::cmp_for()`.
+    /// [Wikipedia][1] has a good explanation of the algorithm.
+    ///
+    /// `depth` is the number of plies left to search. Passing the number of blank squares searches
+    /// every game to its end. If the search stops early, the position is scored `StillPlaying`.
+    /// `blank_squares_remaining` of the result always counts the actual blank squares left on the
+    /// board at the end of the line, which is what makes faster wins and slower losses preferred.
+    ///
+    /// The window is given in absolute (X is maximizing) terms:
+    ///
+    ///  - `alpha` is the result `player` is already guaranteed elsewhere in the tree
+    ///  - `beta` is the best result the opponent will allow `player` to reach
+    ///
+    /// so `alpha` is never better for `player` than `beta`. The root should be searched with
+    /// `MoveScoreTurns::worst_for(player)` as `alpha` and `MoveScoreTurns::worst_for(opponent)` as
+    /// `beta`, which gives an exact result. What is returned when the true value lies outside a
+    /// narrower window is set by the `BoundSemantics` of the `SearchContext`. The order in which
+    /// moves are tried, and the statistics collected along the way, are also handled by the
+    /// `SearchContext`.
+    ///
+    /// [1]: https://en.wikipedia.org/wiki/Alpha-beta_pruning
+    pub fn alpha_beta(
         &self,
         player: &Player,
         depth: u32,
         alpha: &MoveScoreTurns,
         beta: &MoveScoreTurns,
+        context: &mut SearchContext,
     ) -> MoveScoreTurns {
+        context.visit(depth);
+        context.open_position(self, player);
+
         // Base case
         if depth == 0 || self.game_status != GameStatus::StillPlaying {
-            return MoveScoreTurns {
+            let value = MoveScoreTurns {
                 score: self.game_status,
-                blank_squares_remaining: depth,
+                blank_squares_remaining: self.blank_squares_remaining(),
                 ..Default::default() // Point is immediately overwritten, so initialize it with
                                      // something convenient
             };
+            context.close_position(&value);
+            return value;
         }
 
         // Recursive case
-        match player {
-            // Maximizing player
-            Player::X => {
-                // Initialize values
-                let mut value = MoveScoreTurns::MIN;
-                let mut new_alpha = *alpha;
-                let mut new_value; // This value is outside the loop due to lifetime considerations
-
-                for blank_square in &self.blank_squares_set.0 {
-                    // Create a copy of the board to explore potential moves and their outcomes
-                    let mut new_board = self.clone();
-                    let _ = new_board.insert(blank_square, player.square_type());
-                    new_value = new_board.alpha_beta(&player.other(), depth - 1, &new_alpha, beta);
-                    new_value.player_move = *blank_square; // Overwrite the returned board.player_move value
-                                                           // to the move that was most recently played.
-                                                           // This associates the correct return value with the correct move.
-
-                    value = std::cmp::max(value, new_value);
-                    if new_value > *beta {
-                        break; // This is where the pruning takes place
-                    }
-                    new_alpha = std::cmp::max(value, new_alpha);
-                }
-                value
-            }
-            // Minimizing Player
-            Player::O => {
-                // Initialize values
-                let mut value = MoveScoreTurns::MAX;
-                let mut new_beta = *beta;
-                let mut new_value; // This value is outside the loop due to lifetime considerations
-
-                for blank_square in &self.blank_squares_set.0 {
-                    // Create a copy of the board to explore potential moves and their outcomes
-                    let mut new_board = self.clone();
-                    let _ = new_board.insert(blank_square, player.square_type());
-                    new_value = new_board.alpha_beta(&player.other(), depth - 1, alpha, &new_beta);
-                    new_value.player_move = *blank_square; // Overwrite the returned board.player_move value
-                                                           // to the move that was most recently played.
-                                                           // This associates the correct return value with the correct move.
-
-                    value = std::cmp::min(value, new_value);
-                    if new_value < *alpha {
-                        break; // This is where the pruning takes place
-                    }
+        let better = |a: &MoveScoreTurns, b: &MoveScoreTurns| {
+            a.cmp_for(b, player) == std::cmp::Ordering::Greater
+        };
+        let mut new_alpha = *alpha;
+        let mut value: Option<MoveScoreTurns> = None;
 
-                    new_beta = std::cmp::min(value, new_beta);
-                }
-                value
+        let moves: Vec<(Point, SquareType)> = context
+            .order_moves(self, player)
+            .into_iter()
+            .flat_map(|point| {
+                self.playable_pieces(player)
+                    .into_iter()
+                    .map(move |piece| (point, piece))
+            })
+            .collect();
+        for (i, &(blank_square, piece)) in moves.iter().enumerate() {
+            // Create a copy of the board to explore potential moves and their outcomes
+            let mut new_board = self.clone();
+            let _ = new_board.place(&blank_square, piece, player);
+
+            // From the opponent's point of view the window is reversed: what the opponent is
+            // guaranteed is what we allow them (`beta`), and the best they can hope for is what we
+            // are already guaranteed (`new_alpha`)
+            let mut new_value =
+                new_board.alpha_beta(&player.other(), depth - 1, beta, &new_alpha, context);
+            new_value.player_move = blank_square; // Associate the returned value with the move
+                                                  // that was most recently played
+            new_value.piece = piece;
+
+            // Of several equally good moves, the first one searched is kept
+            if value.is_none_or(|value| better(&new_value, &value)) {
+                value = Some(new_value);
+                context.update_principal_variation(depth, blank_square, piece);
             }
+            if better(&new_value, &new_alpha) {
+                new_alpha = new_value;
+            }
+            if !better(beta, &new_alpha) {
+                context.record_cutoff(self, player, &blank_square, &moves[i + 1..], depth);
+                break; // This is where the pruning takes place
+            }
+        }
+
+        let value = value.expect("a position that is still being played has a blank square");
+        let value = match context.bound_semantics {
+            BoundSemantics::FailSoft => value,
+            BoundSemantics::FailHard if !better(&value, alpha) => *alpha,
+            BoundSemantics::FailHard if !better(beta, &value) => *beta,
+            BoundSemantics::FailHard => value,
+        };
+        context.close_position(&value);
+        value
+    }
+
+    /// A root-parallel version of `alpha_beta()` that spreads the moves available at the root
+    /// across `threads` scoped threads, returning the best move along with the line of play
+    /// expected to follow it.
+    ///
+    /// Each thread repeatedly takes the next unsearched root move and runs the ordinary
+    /// sequential `alpha_beta()` on it with its own fork of `context`. The best result found so
+    /// far by *any* thread is shared and used as the bound for every new root move, so the threads
+    /// still prune each other's work. Root results that fall outside that bound can only be worse
+    /// than the shared best, so the returned score and number of turns are the same as those of
+    /// the sequential search. When several moves are equally good, which of them is returned may
+    /// differ. The statistics of every thread are merged back into `context`.
+    pub fn alpha_beta_parallel(
+        &self,
+        player: &Player,
+        depth: u32,
+        threads: usize,
+        context: &mut SearchContext,
+    ) -> SearchResult {
+        if threads <= 1 || depth == 0 || self.game_status != GameStatus::StillPlaying {
+            let move_score_turns = self.alpha_beta(
+                player,
+                depth,
+                &MoveScoreTurns::worst_for(player),
+                &MoveScoreTurns::worst_for(&player.other()),
+                context,
+            );
+            return SearchResult::new(*player, move_score_turns, context.principal_variation(0));
         }
+
+        context.visit(depth); // The root itself
+        let root_moves: Vec<(Point, SquareType)> = context
+            .order_moves(self, player)
+            .into_iter()
+            .flat_map(|point| {
+                self.playable_pieces(player)
+                    .into_iter()
+                    .map(move |piece| (point, piece))
+            })
+            .collect();
+        let next_move = AtomicUsize::new(0);
+        let best = Mutex::new((MoveScoreTurns::worst_for(player), Vec::new()));
+
+        let thread_stats: Vec<SearchStats> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads.min(root_moves.len()))
+                .map(|_| {
+                    let mut thread_context = context.fork();
+                    let (root_moves, next_move, best) = (&root_moves, &next_move, &best);
+                    scope.spawn(move || {
+                        while let Some(&(root_move, piece)) =
+                            root_moves.get(next_move.fetch_add(1, SeqCst))
+                        {
+                            // The shared best is the root's `alpha`, so it is the `beta` of the
+                            // opponent's reply
+                            let alpha = best.lock().unwrap().0;
+                            let beta = MoveScoreTurns::worst_for(&player.other());
+
+                            let mut new_board = self.clone();
+                            let _ = new_board.place(&root_move, piece, player);
+                            let mut new_value = new_board.alpha_beta(
+                                &player.other(),
+                                depth - 1,
+                                &beta,
+                                &alpha,
+                                &mut thread_context,
+                            );
+                            new_value.player_move = root_move;
+                            new_value.piece = piece;
+
+                            let mut best = best.lock().unwrap();
+                            if new_value.cmp_for(&best.0, player) == std::cmp::Ordering::Greater {
+                                let mut line = vec![(root_move, piece)];
+                                line.extend_from_slice(thread_context.principal_variation(1));
+                                *best = (new_value, line);
+                            }
+                        }
+                        thread_context.stats
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        for stats in &thread_stats {
+            context.stats.merge(stats);
+        }
+        let (move_score_turns, line) = best.into_inner().unwrap();
+        SearchResult::new(*player, move_score_turns, &line)
     }
 
-    fn update_status(&mut self) {
+    /// The pieces `player` may place: both under wild rules, otherwise just their own
+    pub fn playable_pieces(&self, player: &Player) -> Vec<SquareType> {
+        if self.rules.wild {
+            vec![player.square_type(), player.other().square_type()]
+        } else {
+            vec![player.square_type()]
+        }
+    }
+
+    /// The number of squares that have already been played in. This is also the number of plies
+    /// played so far, since blocked squares don't count.
+    pub fn squares_filled(&self) -> usize {
+        self.content
+            .iter()
+            .flatten()
+            .filter(|square| square.owner().is_some())
+            .count()
+    }
+
+    /// Whether playing `square_type` at `point` would complete a row, column, or diagonal of it
+    pub fn completes_line(&self, point: &Point, square_type: &SquareType) -> bool {
+        let owned =
+            |x: usize, y: usize| (x, y) == (point.x, point.y) || self.content[x][y] == *square_type;
+        let n = self.size;
+        if self.rules.torus {
+            // Every square is on one diagonal of each direction
+            return (0..n).all(|y| owned(point.x, y))
+                || (0..n).all(|x| owned(x, point.y))
+                || (0..n).all(|i| owned(i, (i + n + point.y - point.x) % n))
+                || (0..n).all(|i| owned(i, (n + point.x + point.y - i) % n));
+        }
+        (0..n).all(|y| owned(point.x, y))
+            || (0..n).all(|x| owned(x, point.y))
+            || (point.x == point.y && (0..n).all(|i| owned(i, i)))
+            || (point.x + point.y == n - 1 && (0..n).all(|i| owned(i, n - 1 - i)))
+    }
+
+    /// The number of rows, columns, and diagonals passing through `point`. On a 3x3 board this is
+    /// 4 for the centre, 3 for the corners, and 2 for the edges. On a torus it is 4 everywhere.
+    pub fn lines_through(&self, point: &Point) -> usize {
+        let n = self.size;
+        if self.rules.torus {
+            return 4;
+        }
+        2 + usize::from(point.x == point.y) + usize::from(point.x + point.y == n - 1)
+    }
+
+    // `check_status()` reports a completed line as a win for the owner of its pieces. Under wild
+    // rules the line belongs to whoever completed it (`mover`), and under misère rules the player
+    // who completed it loses instead.
+    fn update_status(&mut self, mover: &Player) {
         if GameStatus::StillPlaying == self.game_status {
-            self.game_status = self.check_status();
+            let winner = match self.reachable_status() {
+                GameStatus::XWin | GameStatus::OWin if self.rules.wild => *mover,
+                GameStatus::XWin => Player::X,
+                GameStatus::OWin => Player::O,
+                game_status => {
+                    self.game_status = game_status;
+                    return;
+                }
+            };
+            let winner = if self.rules.misere {
+                winner.other()
+            } else {
+                winner
+            };
+            self.game_status = match winner {
+                Player::X => GameStatus::XWin,
+                Player::O => GameStatus::OWin,
+            };
+        }
+    }
+
+    /// Adds a new `SquareType` to the `Board` and removes the corresponding value from the `blank_squares_set`
+    pub fn insert(&mut self, point: &Point, value: SquareType) -> Result<(), String> {
+        match value.owner() {
+            Some(player) => self.place(point, value, &player),
+            None => Err(format!("A blank square can't be played at {point}")),
+        }
+    }
+
+    /// Blocks the blank square at `point` so neither player can play there. Blocking a square
+    /// isn't a move, so it is meant to be done before the game starts.
+    pub fn block(&mut self, point: &Point) -> Result<(), String> {
+        if !self.blank_squares_set.0.remove(point) {
+            return Err(format!("Only a blank square can be blocked. Got {point}"));
+        }
+        self.content[point.x][point.y] = SquareType::Blocked;
+        // A blocked square can't complete a line, but it can leave no blank squares, or under
+        // gravity none that can be reached
+        if self.game_status == GameStatus::StillPlaying {
+            self.game_status = self.reachable_status();
+        }
+        Ok(())
+    }
+
+    /// Takes the piece at `point` off the board, leaving the square blank, and returns it. Pieces
+    /// can only be taken off the board of an unfinished game.
+    pub fn remove(&mut self, point: &Point) -> Result<SquareType, String> {
+        if self.game_status != GameStatus::StillPlaying {
+            return Err("The game is over, so no pieces can be taken off the board".to_string());
+        }
+        let piece = self
+            .content
+            .get(point.x)
+            .and_then(|row| row.get(point.y))
+            .copied()
+            .unwrap_or_default();
+        if piece.owner().is_none() {
+            return Err(format!("There is no piece at {point}"));
         }
+        self.content[point.x][point.y] = SquareType::B;
+        self.blank_squares_set.0.insert(*point);
+        Ok(piece)
     }
 
-    // Adds a new `SquareType` to the `Board` and removes the corresponding value from the `blank_squares_set`
-    fn insert(&mut self, point: &Point, value: SquareType) -> Result<(), String> {
+    /// `player` places `piece` at `point`. Except in Wild tic-tac-toe, the piece is always the
+    /// player's own (see `playable_pieces()`).
+    pub fn place(
+        &mut self,
+        point: &Point,
+        piece: SquareType,
+        player: &Player,
+    ) -> Result<(), String> {
+        if self.rules.gravity
+            && self.blank_squares_set.0.contains(point)
+            && self.drop_point(point.y) != Some(*point)
+        {
+            return Err(format!(
+                "Pieces drop to the bottom of their column, so {point} can't be played yet"
+            ));
+        }
         if let Some(point) = self.blank_squares_set.0.take(point) {
-            self.content[point.x][point.y] = value;
-            self.update_status();
+            self.content[point.x][point.y] = piece;
+            self.update_status(player);
             Ok(())
         } else {
             Err(format!(
@@ -342,6 +613,17 @@ impl Board {
         }
     }
 
+    // `check_status()`, except that under gravity a blank square below a blocked one can never be
+    // played, so once only such squares are left the game is a draw
+    fn reachable_status(&self) -> GameStatus {
+        match self.check_status() {
+            GameStatus::StillPlaying if self.rules.gravity && self.legal_moves().is_empty() => {
+                GameStatus::Draw
+            }
+            game_status => game_status,
+        }
+    }
+
     // In order for a game of tic-tac-toe to be won, a player needs to have marks in either an
     // entire horizontal row, an entire vertical column, or an entire diagonal. This implementation
     // does this checking in three parts and combines the results of the partial checks.
@@ -403,46 +685,48 @@ impl Board {
 
     // Operates in a similar manner to `check_rows()` but with additional set-up code to get the
     // right `SquareType`s to easily `reduce`
+    //
+    // On a torus the diagonals wrap around the edges, so a diagonal starts in every column of the
+    // top row rather than just the corners. (Rows and columns that wrap around are the same rows
+    // and columns, so only the diagonals change.)
     fn check_diag(&self) -> GameStatus {
         if self.content.len() != self.content[0].len() {
             todo!("Not a square board");
         }
 
-        let l_to_r_diag = self
-            .content
-            .iter()
-            .enumerate()
-            .map(|(i, e)| &e[i])
-            .map(PartialLineStatus::PartialLine)
-            .reduce(|acc, e| PartialLineStatus::combine(&acc, &e))
-            .unwrap()
-            .upgrade();
+        let n = self.size;
+        let offsets = if self.rules.torus { n } else { 1 };
+        let diag_status = |column: &dyn Fn(usize) -> usize| {
+            (0..n)
+                .map(|i| &self.content[i][column(i)])
+                .map(PartialLineStatus::PartialLine)
+                .reduce(|acc, e| PartialLineStatus::combine(&acc, &e))
+                .unwrap()
+                .upgrade()
+        };
 
-        let r_to_l_diag = self
-            .content
-            .iter()
-            .rev()
-            .enumerate()
-            .map(|(i, e)| &e[i])
-            .map(PartialLineStatus::PartialLine)
-            .reduce(|acc, e| PartialLineStatus::combine(&acc, &e))
+        (0..offsets)
+            .flat_map(|k| {
+                let l_to_r_diag = diag_status(&|i| (i + k) % n);
+                let r_to_l_diag = diag_status(&|i| (n - 1 - i + k) % n);
+                [l_to_r_diag, r_to_l_diag]
+            })
+            .reduce(GameStatus::combine)
             .unwrap()
-            .upgrade();
-
-        GameStatus::combine(l_to_r_diag, r_to_l_diag)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::cmp::Ordering;
+    use std::collections::HashMap;
 
     /// Used to easily initialize tic-tac-toe boards for testing purposes.
     /// Values within a row are separated by a space. Each row is separated
     /// by a space and a vertical bar ('|') character.
-
     impl Board {
-        fn from_string(string: &str) -> Self {
+        pub(crate) fn from_string(string: &str) -> Self {
             let rows: Vec<&str> = string.split_terminator('|').collect();
             let size = rows.len();
             let mut cols;
@@ -465,9 +749,14 @@ mod tests {
                 col_vec.push(row_vec);
             }
 
-            let mut temp_board =
-                Board::new(col_vec, size, blank_squares_set, GameStatus::StillPlaying);
-            temp_board.update_status();
+            let mut temp_board = Board::new(
+                col_vec,
+                size,
+                blank_squares_set,
+                GameStatus::StillPlaying,
+                Rules::default(),
+            );
+            temp_board.update_status(&Player::X); // Only wild rules need to know who moved
             temp_board
         }
     }
@@ -655,4 +944,580 @@ mod tests {
         );
         assert_eq!(still_playing_board.check_diag(), GameStatus::StillPlaying);
     }
+
+    #[test]
+    fn parallel_search_matches_sequential_search() {
+        let positions = [
+            "B B B | B B B | B B B",
+            "X B B | B O B | B B B",
+            "X B B | B O B | B B X",
+            "X O B | B X B | B B B",
+            "O X B | B B B | B B B",
+        ];
+        for position in positions {
+            let board = Board::from_string(position);
+            let player = if board.blank_squares_remaining() % 2 == 1 {
+                Player::X
+            } else {
+                Player::O
+            };
+            let depth = board.blank_squares_remaining();
+            let sequential = board.alpha_beta(
+                &player,
+                depth,
+                &MoveScoreTurns::worst_for(&player),
+                &MoveScoreTurns::worst_for(&player.other()),
+                &mut SearchContext::new(true),
+            );
+            for threads in 1..=4 {
+                let parallel = board
+                    .alpha_beta_parallel(&player, depth, threads, &mut SearchContext::new(true))
+                    .move_score_turns;
+                assert_eq!(parallel.score, sequential.score, "{position}");
+                assert_eq!(
+                    parallel.blank_squares_remaining, sequential.blank_squares_remaining,
+                    "{position}"
+                );
+            }
+        }
+    }
+
+    // Every position reachable from the empty board, paired with the player to move. Either player
+    // may move first, so both starts are included.
+    fn reachable_positions() -> Vec<(Board, Player)> {
+        let mut seen = HashSet::new();
+        let mut positions = Vec::new();
+        let empty_board = Board::initialize_blank_board(3);
+        let mut stack = vec![(empty_board.clone(), Player::X), (empty_board, Player::O)];
+        while let Some((board, player)) = stack.pop() {
+            if !seen.insert((board.to_string(), player)) {
+                continue;
+            }
+            if board.game_status == GameStatus::StillPlaying {
+                for point in board.blank_squares() {
+                    let mut new_board = board.clone();
+                    new_board.insert(&point, player.square_type()).unwrap();
+                    stack.push((new_board, player.other()));
+                }
+            }
+            positions.push((board, player));
+        }
+        positions
+    }
+
+    // A plain minmax search without any pruning, memoized on the rendered board, to check
+    // `alpha_beta()` against
+    fn minimax(
+        board: &Board,
+        player: &Player,
+        memo: &mut HashMap<(String, Player), MoveScoreTurns>,
+    ) -> MoveScoreTurns {
+        if board.game_status != GameStatus::StillPlaying {
+            return MoveScoreTurns {
+                score: board.game_status,
+                blank_squares_remaining: board.blank_squares_remaining(),
+                ..Default::default()
+            };
+        }
+        let key = (board.to_string(), *player);
+        if let Some(value) = memo.get(&key) {
+            return *value;
+        }
+
+        let value = board
+            .blank_squares()
+            .iter()
+            .map(|point| {
+                let mut new_board = board.clone();
+                new_board.insert(point, player.square_type()).unwrap();
+                minimax(&new_board, &player.other(), memo)
+            })
+            .max_by(|a, b| a.cmp_for(b, player))
+            .unwrap();
+        memo.insert(key, value);
+        value
+    }
+
+    fn search(
+        board: &Board,
+        player: &Player,
+        alpha: &MoveScoreTurns,
+        beta: &MoveScoreTurns,
+        move_ordering: bool,
+        bound_semantics: BoundSemantics,
+    ) -> MoveScoreTurns {
+        let mut context = SearchContext::new(move_ordering).with_bound_semantics(bound_semantics);
+        board.alpha_beta(
+            player,
+            board.blank_squares_remaining(),
+            alpha,
+            beta,
+            &mut context,
+        )
+    }
+
+    #[test]
+    fn depth_limited_search_ranks_an_open_position_like_a_draw_for_both_sides() {
+        // A search one move deep only finds positions that are still being played. With a draw
+        // already guaranteed, neither side counts them as an improvement on it, so a fail-hard
+        // search returns the draw for X just as it does for O.
+        let board = Board::initialize_blank_board(3);
+        let draw = MoveScoreTurns {
+            score: GameStatus::Draw,
+            ..Default::default()
+        };
+        for player in [Player::X, Player::O] {
+            let mut context =
+                SearchContext::new(false).with_bound_semantics(BoundSemantics::FailHard);
+            let value = board.alpha_beta(
+                &player,
+                1,
+                &draw,
+                &MoveScoreTurns::worst_for(&player.other()),
+                &mut context,
+            );
+            assert_eq!(value.score, GameStatus::Draw, "{player}");
+        }
+    }
+
+    #[test]
+    fn alpha_beta_matches_minimax_on_every_reachable_position() {
+        let mut memo = HashMap::new();
+        for (board, player) in reachable_positions() {
+            if board.game_status != GameStatus::StillPlaying {
+                continue;
+            }
+            let exact = minimax(&board, &player, &mut memo);
+            assert_ne!(exact.score, GameStatus::StillPlaying);
+
+            for (move_ordering, bound_semantics) in [
+                (false, BoundSemantics::FailSoft),
+                (true, BoundSemantics::FailSoft),
+                (true, BoundSemantics::FailHard),
+            ] {
+                let result = search(
+                    &board,
+                    &player,
+                    &MoveScoreTurns::worst_for(&player),
+                    &MoveScoreTurns::worst_for(&player.other()),
+                    move_ordering,
+                    bound_semantics,
+                );
+                // The ordering compares the score and, for wins, the number of turns
+                assert_eq!(
+                    result.cmp(&exact),
+                    Ordering::Equal,
+                    "\n{board}\n{move_ordering} {bound_semantics:?}"
+                );
+                assert_eq!(
+                    result.blank_squares_remaining, exact.blank_squares_remaining,
+                    "\n{board}"
+                );
+
+                // The chosen move must actually achieve the result
+                let mut new_board = board.clone();
+                new_board
+                    .insert(&result.player_move, player.square_type())
+                    .unwrap();
+                let achieved = minimax(&new_board, &player.other(), &mut memo);
+                assert_eq!(achieved.cmp(&exact), Ordering::Equal);
+            }
+        }
+    }
+
+    #[test]
+    fn alpha_beta_respects_bound_semantics() {
+        // Every distinct result a 3x3 game can have
+        let mut results: Vec<MoveScoreTurns> = (0..=9)
+            .flat_map(|blank_squares_remaining| {
+                [GameStatus::OWin, GameStatus::XWin].map(|score| MoveScoreTurns {
+                    score,
+                    blank_squares_remaining,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        results.push(MoveScoreTurns {
+            score: GameStatus::Draw,
+            ..Default::default()
+        });
+        results.sort();
+
+        let mut memo = HashMap::new();
+        for (i, (board, player)) in reachable_positions().into_iter().enumerate() {
+            if board.game_status != GameStatus::StillPlaying || i % 7 != 0 {
+                continue;
+            }
+            let exact = minimax(&board, &player, &mut memo);
+            let ranks = |value: &MoveScoreTurns| value.cmp_for(&exact, &player);
+
+            // A few windows of different widths in different places
+            for (low, high) in [(i % 21, (i + 5) % 21), (i % 13, i % 13 + 8), (9, 11)] {
+                let (low, high) = (results[low.min(high)], results[low.max(high)]);
+                let (alpha, beta) = match player {
+                    Player::X => (low, high),
+                    Player::O => (high, low),
+                };
+
+                let hard = search(
+                    &board,
+                    &player,
+                    &alpha,
+                    &beta,
+                    true,
+                    BoundSemantics::FailHard,
+                );
+                let soft = search(
+                    &board,
+                    &player,
+                    &alpha,
+                    &beta,
+                    true,
+                    BoundSemantics::FailSoft,
+                );
+
+                // Fail-hard results are clamped to the window
+                match (ranks(&alpha), ranks(&beta)) {
+                    (Ordering::Greater | Ordering::Equal, _) => {
+                        assert_eq!(hard.cmp(&alpha), Ordering::Equal)
+                    }
+                    (_, Ordering::Less | Ordering::Equal) => {
+                        assert_eq!(hard.cmp(&beta), Ordering::Equal)
+                    }
+                    _ => assert_eq!(hard.cmp(&exact), Ordering::Equal),
+                }
+
+                // Fail-soft results are bounds on the exact result when outside the window
+                if soft.cmp_for(&alpha, &player) != Ordering::Greater {
+                    assert_ne!(ranks(&soft), Ordering::Less, "\n{board}");
+                } else if soft.cmp_for(&beta, &player) != Ordering::Less {
+                    assert_ne!(ranks(&soft), Ordering::Greater, "\n{board}");
+                } else {
+                    assert_eq!(ranks(&soft), Ordering::Equal, "\n{board}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn alpha_beta_prefers_fastest_win() {
+        let board = Board::from_string(
+            "X X B |
+             B O B |
+             B O B",
+        );
+        let result = search(
+            &board,
+            &Player::X,
+            &MoveScoreTurns::MIN,
+            &MoveScoreTurns::MAX,
+            true,
+            BoundSemantics::FailHard,
+        );
+        assert_eq!(result.score, GameStatus::XWin);
+        assert_eq!(result.blank_squares_remaining, 4);
+        assert_eq!(result.player_move, Point { x: 0, y: 2 });
+    }
+
+    #[test]
+    fn misere_line_loses() {
+        let mut board = Board::with_rules(
+            3,
+            Rules {
+                misere: true,
+                ..Default::default()
+            },
+        );
+        for (point, square_type) in [
+            (Point { x: 0, y: 0 }, SquareType::X),
+            (Point { x: 1, y: 1 }, SquareType::O),
+            (Point { x: 0, y: 1 }, SquareType::X),
+            (Point { x: 2, y: 2 }, SquareType::O),
+        ] {
+            board.insert(&point, square_type).unwrap();
+            assert_eq!(board.game_status, GameStatus::StillPlaying);
+        }
+        board.insert(&Point { x: 0, y: 2 }, SquareType::X).unwrap();
+        assert_eq!(board.game_status, GameStatus::OWin);
+    }
+
+    #[test]
+    fn misere_engine_avoids_completing_a_line() {
+        let mut board = Board::with_rules(
+            3,
+            Rules {
+                misere: true,
+                ..Default::default()
+            },
+        );
+        for (point, square_type) in [
+            (Point { x: 0, y: 0 }, SquareType::X),
+            (Point { x: 1, y: 0 }, SquareType::O),
+            (Point { x: 0, y: 1 }, SquareType::X),
+            (Point { x: 1, y: 1 }, SquareType::O),
+        ] {
+            board.insert(&point, square_type).unwrap();
+        }
+        let result = search(
+            &board,
+            &Player::X,
+            &MoveScoreTurns::MIN,
+            &MoveScoreTurns::MAX,
+            true,
+            BoundSemantics::FailSoft,
+        );
+        assert_ne!(result.player_move, Point { x: 0, y: 2 });
+        assert_ne!(result.score, GameStatus::OWin);
+
+        // Misère tic-tac-toe is a draw with best play
+        let empty_board = Board::with_rules(
+            3,
+            Rules {
+                misere: true,
+                ..Default::default()
+            },
+        );
+        let result = search(
+            &empty_board,
+            &Player::X,
+            &MoveScoreTurns::MIN,
+            &MoveScoreTurns::MAX,
+            true,
+            BoundSemantics::FailSoft,
+        );
+        assert_eq!(result.score, GameStatus::Draw);
+    }
+
+    #[test]
+    fn wild_line_wins_for_the_player_completing_it() {
+        let mut board = Board::with_rules(
+            3,
+            Rules {
+                wild: true,
+                ..Default::default()
+            },
+        );
+        board
+            .place(&Point { x: 0, y: 0 }, SquareType::O, &Player::X)
+            .unwrap();
+        board
+            .place(&Point { x: 1, y: 1 }, SquareType::X, &Player::O)
+            .unwrap();
+        board
+            .place(&Point { x: 0, y: 1 }, SquareType::O, &Player::X)
+            .unwrap();
+        assert_eq!(board.game_status, GameStatus::StillPlaying);
+        board
+            .place(&Point { x: 0, y: 2 }, SquareType::O, &Player::O)
+            .unwrap();
+        assert_eq!(board.game_status, GameStatus::OWin);
+    }
+
+    #[test]
+    fn wild_engine_completes_a_line_of_either_piece() {
+        let mut board = Board::with_rules(
+            3,
+            Rules {
+                wild: true,
+                ..Default::default()
+            },
+        );
+        board
+            .place(&Point { x: 0, y: 0 }, SquareType::O, &Player::X)
+            .unwrap();
+        board
+            .place(&Point { x: 2, y: 2 }, SquareType::X, &Player::O)
+            .unwrap();
+        board
+            .place(&Point { x: 0, y: 1 }, SquareType::O, &Player::X)
+            .unwrap();
+        board
+            .place(&Point { x: 1, y: 0 }, SquareType::X, &Player::O)
+            .unwrap();
+        let result = search(
+            &board,
+            &Player::X,
+            &MoveScoreTurns::MIN,
+            &MoveScoreTurns::MAX,
+            true,
+            BoundSemantics::FailSoft,
+        );
+        assert_eq!(result.score, GameStatus::XWin);
+        assert_eq!(result.blank_squares_remaining, 4);
+        assert_eq!(result.piece, SquareType::O);
+        assert_eq!(result.player_move, Point { x: 0, y: 2 });
+    }
+
+    #[test]
+    fn gravity_drops_pieces_to_the_bottom_of_the_column() {
+        let mut board = Board::with_rules(
+            3,
+            Rules {
+                gravity: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            board.legal_moves(),
+            vec![
+                Point { x: 2, y: 0 },
+                Point { x: 2, y: 1 },
+                Point { x: 2, y: 2 }
+            ]
+        );
+        assert!(board.insert(&Point { x: 0, y: 1 }, SquareType::X).is_err());
+
+        for square_type in [SquareType::X, SquareType::O, SquareType::X] {
+            let point = board.drop_point(1).unwrap();
+            board.insert(&point, square_type).unwrap();
+        }
+        assert_eq!(board.drop_point(1), None);
+        assert_eq!(board.position(), "BXB/BOB/BXB");
+        assert_eq!(
+            board.legal_moves(),
+            vec![Point { x: 2, y: 0 }, Point { x: 2, y: 2 }]
+        );
+    }
+
+    #[test]
+    fn gravity_engine_only_plays_legal_moves() {
+        let rules = Rules {
+            gravity: true,
+            ..Default::default()
+        };
+        // Dropping into the middle column completes the bottom row
+        let board = Board::from_position("BBB/BBB/XBX", rules).unwrap();
+        let result = search(
+            &board,
+            &Player::X,
+            &MoveScoreTurns::MIN,
+            &MoveScoreTurns::MAX,
+            true,
+            BoundSemantics::FailSoft,
+        );
+        assert_eq!(result.player_move, Point { x: 2, y: 1 });
+        assert_eq!(result.score, GameStatus::XWin);
+
+        // Pieces can't float above blank squares
+        let board = Board::from_position("BBB/OBB/XBX", rules).unwrap();
+        assert!(board.legal_moves().contains(&Point { x: 0, y: 0 }));
+        assert!(Board::from_position("BBB/OBB/BBX", rules).is_err());
+    }
+
+    #[test]
+    fn gravity_game_is_drawn_when_only_squares_below_blocks_are_left() {
+        let rules = Rules {
+            gravity: true,
+            ..Default::default()
+        };
+        // The blank square under the blocked one can never be played
+        let mut board = Board::from_position("BXO/#OX/BOX", rules).unwrap();
+        assert_eq!(board.legal_moves(), vec![Point { x: 0, y: 0 }]);
+        board.insert(&Point { x: 0, y: 0 }, SquareType::X).unwrap();
+        assert_eq!(board.game_status(), GameStatus::Draw);
+        assert_eq!(board.blank_squares_remaining(), 1);
+
+        // So the whole game can be searched (as with `--gravity --blocked 1,0`)
+        let mut board = Board::with_rules(3, rules);
+        board.block(&Point { x: 1, y: 0 }).unwrap();
+        let result = search(
+            &board,
+            &Player::X,
+            &MoveScoreTurns::MIN,
+            &MoveScoreTurns::MAX,
+            true,
+            BoundSemantics::FailSoft,
+        );
+        assert_ne!(result.score, GameStatus::StillPlaying);
+    }
+
+    #[test]
+    fn torus_diagonals_wrap_around() {
+        let torus = Rules {
+            torus: true,
+            ..Default::default()
+        };
+        // The positions, their status on a torus, and whether the line only exists on a torus
+        for (position, status, wraps) in [
+            // Broken diagonals on a 3x3 board
+            ("BXB/BOX/XOB", GameStatus::XWin, true),
+            ("OXB/XBB/BBX", GameStatus::XWin, true),
+            // ... and on a 4x4 board
+            ("BBOB/BBBO/OBBB/BOBB", GameStatus::OWin, true),
+            ("BOBB/OBBB/BBBO/BBOB", GameStatus::OWin, true),
+            ("XBBB/BXBB/BBXB/BBBX", GameStatus::XWin, false),
+            ("BOBB/BBOB/BBBB/OBBB", GameStatus::StillPlaying, false),
+        ] {
+            let board = Board::from_position(position, torus).unwrap();
+            assert_eq!(board.game_status, status, "{position}");
+            if wraps {
+                let board: Board = position.parse().unwrap();
+                assert_eq!(board.game_status, GameStatus::StillPlaying, "{position}");
+            }
+        }
+    }
+
+    #[test]
+    fn torus_engine_sees_broken_diagonals() {
+        let board = Board::from_position(
+            "BXB/OBX/BOB",
+            Rules {
+                torus: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(board.completes_line(&Point { x: 2, y: 0 }, &SquareType::X));
+        let result = search(
+            &board,
+            &Player::X,
+            &MoveScoreTurns::MIN,
+            &MoveScoreTurns::MAX,
+            true,
+            BoundSemantics::FailSoft,
+        );
+        assert_eq!(result.player_move, Point { x: 2, y: 0 });
+        assert_eq!(result.score, GameStatus::XWin);
+    }
+
+    #[test]
+    fn blocked_squares_are_neither_blank_nor_owned() {
+        let board: Board = "X#X/BOB/BBB".parse().unwrap();
+        assert_eq!(board.square(&Point { x: 0, y: 1 }), SquareType::Blocked);
+        assert_eq!(board.position(), "X#X/BOB/BBB");
+        assert!(!board.legal_moves().contains(&Point { x: 0, y: 1 }));
+        assert_eq!(board.squares_filled(), 3);
+        assert!(board.to_string().contains('#'));
+
+        // A line with a blocked square in it can't be won, so this full board is a draw
+        let board: Board = "XO#/OXX/XXO".parse().unwrap();
+        assert_eq!(board.game_status, GameStatus::Draw);
+
+        // Under gravity, pieces come to rest on a blocked square
+        let mut board = Board::with_rules(
+            3,
+            Rules {
+                gravity: true,
+                ..Default::default()
+            },
+        );
+        board.block(&Point { x: 1, y: 0 }).unwrap();
+        assert_eq!(board.drop_point(0), Some(Point { x: 0, y: 0 }));
+        assert!(board.block(&Point { x: 1, y: 0 }).is_err());
+    }
+
+    #[test]
+    fn engine_plays_around_blocked_squares() {
+        // X can't finish the top row, so it has to stop O's middle row instead
+        let board: Board = "XX#/OOB/BBB".parse().unwrap();
+        let result = search(
+            &board,
+            &Player::X,
+            &MoveScoreTurns::MIN,
+            &MoveScoreTurns::MAX,
+            true,
+            BoundSemantics::FailSoft,
+        );
+        assert_eq!(result.player_move, Point { x: 1, y: 2 });
+    }
 }
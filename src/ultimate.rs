@@ -0,0 +1,459 @@
+use crate::board_info::{Player, Point};
+use crate::config::Config;
+use crate::game_variant::{self, GameVariant, Negamax, WIN};
+use crate::scoring::GameStatus;
+use crate::tic_tac_toe_board::Board;
+
+/// Where a move is played in Ultimate tic-tac-toe: which small board, and which square of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UltimateMove {
+    pub board: Point,
+    pub square: Point,
+}
+
+/// Written the way moves are entered, e.g. "0 2 1 1"
+impl std::fmt::Display for UltimateMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let UltimateMove { board, square } = self;
+        write!(f, "{} {} {} {}", board.x, board.y, square.x, square.y)
+    }
+}
+
+/// A game of Ultimate tic-tac-toe: a 3x3 grid of 3x3 boards. Playing in a square of a small board
+/// sends the opponent to the small board in the matching position of the grid, or anywhere if that
+/// board is already decided. Winning a small board claims its square on the big board, and the
+/// first to claim a line of the big board wins.
+#[derive(Debug, Clone)]
+pub struct Ultimate {
+    boards: Vec<Vec<Board>>,
+    /// The small boards each player has won, played as a board of its own
+    big_board: Board,
+    /// The small board the next move has to be played on, if the choice is restricted
+    next_board: Option<Point>,
+    game_status: GameStatus,
+    to_move: Player,
+}
+
+impl Ultimate {
+    pub const SIZE: usize = 3;
+    /// How many plies the computer searches when `--depth` isn't given. The game is far too big
+    /// to search to the end, so the positions there are judged by `evaluate()`.
+    pub const DEFAULT_DEPTH: u32 = 6;
+
+    /// An empty grid, where `first` moves first
+    pub fn new(first: Player) -> Self {
+        Self {
+            boards: vec![vec![Board::initialize_blank_board(Self::SIZE); Self::SIZE]; Self::SIZE],
+            big_board: Board::initialize_blank_board(Self::SIZE),
+            next_board: None,
+            game_status: GameStatus::StillPlaying,
+            to_move: first,
+        }
+    }
+
+    pub fn game_status(&self) -> GameStatus {
+        self.game_status
+    }
+
+    pub fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    /// The small board at `point` of the grid
+    pub fn board(&self, point: &Point) -> &Board {
+        &self.boards[point.x][point.y]
+    }
+
+    /// The small board the next move has to be played on. `None` when any open board may be
+    /// played.
+    pub fn next_board(&self) -> Option<Point> {
+        self.next_board
+    }
+
+    // A small board is decided once it is won or full
+    fn is_open(&self, point: &Point) -> bool {
+        self.board(point).game_status() == GameStatus::StillPlaying
+    }
+
+    /// Every move that can be played next, in a stable order
+    pub fn legal_moves(&self) -> Vec<UltimateMove> {
+        if self.game_status != GameStatus::StillPlaying {
+            return Vec::new();
+        }
+        let boards = match self.next_board {
+            Some(board) => vec![board],
+            None => grid_points().filter(|point| self.is_open(point)).collect(),
+        };
+        boards
+            .into_iter()
+            .flat_map(|board| {
+                self.board(&board)
+                    .blank_squares()
+                    .into_iter()
+                    .map(move |square| UltimateMove { board, square })
+            })
+            .collect()
+    }
+
+    /// The player to move plays `player_move`
+    pub fn play(&mut self, player_move: &UltimateMove) -> Result<(), String> {
+        let UltimateMove { board, square } = *player_move;
+        let player = &self.to_move;
+        if board.x >= Self::SIZE || board.y >= Self::SIZE {
+            return Err(format!(
+                "Not a valid board: {board}. Boards are numbered (0, 0) to (2, 2)"
+            ));
+        }
+        match self.next_board {
+            Some(next_board) if next_board != board => {
+                return Err(format!("This move has to be played on board {next_board}"))
+            }
+            _ if !self.is_open(&board) => {
+                return Err(format!("Board {board} is already decided"));
+            }
+            _ => (),
+        }
+
+        let small_board = &mut self.boards[board.x][board.y];
+        small_board.insert(&square, player.square_type())?;
+        if small_board.game_status() == player_won(player) {
+            self.big_board
+                .insert(&board, player.square_type())
+                .expect("an open small board has a blank square on the big board");
+        }
+
+        self.next_board = Some(square).filter(|square| self.is_open(square));
+        self.to_move = self.to_move.other();
+        // Drawn small boards leave blanks on the big board, so it can't be relied on to call a draw
+        self.game_status = match self.big_board.game_status() {
+            GameStatus::StillPlaying if grid_points().all(|point| !self.is_open(&point)) => {
+                GameStatus::Draw
+            }
+            game_status => game_status,
+        };
+        Ok(())
+    }
+
+    /// How good the position looks for X (positive) or O (negative), for when searching to the end
+    /// of the game isn't feasible. Lines of the big board count for much more than lines of the
+    /// small boards, and lines that are nearly complete for much more than ones just started.
+    pub fn evaluate(&self) -> i32 {
+        match self.game_status {
+            GameStatus::XWin => return WIN,
+            GameStatus::OWin => return -WIN,
+            GameStatus::Draw => return 0,
+            GameStatus::StillPlaying => (),
+        }
+
+        let mut score = 0;
+        for line in LINES {
+            // A drawn small board can't be claimed by either player, so blocks both
+            let owners = line.map(|point| {
+                let point = Point {
+                    x: point.0,
+                    y: point.1,
+                };
+                match self.board(&point).game_status() {
+                    GameStatus::XWin => Some(Some(Player::X)),
+                    GameStatus::OWin => Some(Some(Player::O)),
+                    GameStatus::Draw => None,
+                    GameStatus::StillPlaying => Some(None),
+                }
+            });
+            if owners.iter().all(Option::is_some) {
+                score += 100 * line_value(owners.map(Option::flatten));
+            }
+        }
+        for point in grid_points().filter(|point| self.is_open(point)) {
+            let board = self.board(&point);
+            for line in LINES {
+                score += line_value(line.map(|(x, y)| board.square(&Point { x, y }).owner()));
+            }
+        }
+        score
+    }
+}
+
+/// The grid with each small board drawn as a normal board. Boards that have been won are filled
+/// with the winner's piece, and the boards are separated by double lines.
+impl std::fmt::Display for Ultimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut rows = Vec::new();
+        for big_x in 0..Self::SIZE {
+            for x in 0..Self::SIZE {
+                let row: Vec<String> = (0..Self::SIZE)
+                    .map(|big_y| {
+                        let board = &self.boards[big_x][big_y];
+                        let winner = match board.game_status() {
+                            GameStatus::XWin => Some("X"),
+                            GameStatus::OWin => Some("O"),
+                            GameStatus::Draw | GameStatus::StillPlaying => None,
+                        };
+                        (0..Self::SIZE)
+                            .map(|y| match winner {
+                                Some(winner) => format!(" {winner} "),
+                                None => format!(" {} ", board.square(&Point { x, y })),
+                            })
+                            .collect::<Vec<String>>()
+                            .join("|")
+                    })
+                    .collect();
+                rows.push(row.join("||"));
+                if x + 1 < Self::SIZE {
+                    rows.push(vec!["-".repeat(Self::SIZE * 4 - 1); Self::SIZE].join("||"));
+                }
+            }
+            if big_x + 1 < Self::SIZE {
+                rows.push("=".repeat(Self::SIZE * Self::SIZE * 4 + 1));
+            }
+        }
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+// Every line of a 3x3 board
+const LINES: [[(usize, usize); 3]; 8] = [
+    [(0, 0), (0, 1), (0, 2)],
+    [(1, 0), (1, 1), (1, 2)],
+    [(2, 0), (2, 1), (2, 2)],
+    [(0, 0), (1, 0), (2, 0)],
+    [(0, 1), (1, 1), (2, 1)],
+    [(0, 2), (1, 2), (2, 2)],
+    [(0, 0), (1, 1), (2, 2)],
+    [(0, 2), (1, 1), (2, 0)],
+];
+
+// What a line is worth to X: nothing once both players are in it, and more the closer one player
+// is to completing it
+fn line_value(owners: [Option<Player>; 3]) -> i32 {
+    let count = |player| {
+        owners
+            .iter()
+            .filter(|owner| **owner == Some(player))
+            .count()
+    };
+    match (count(Player::X), count(Player::O)) {
+        (x, 0) => [0, 1, 10, 0][x],
+        (0, o) => -[0, 1, 10, 0][o],
+        _ => 0,
+    }
+}
+
+fn grid_points() -> impl Iterator<Item = Point> {
+    (0..Ultimate::SIZE).flat_map(|x| (0..Ultimate::SIZE).map(move |y| Point { x, y }))
+}
+
+fn player_won(player: &Player) -> GameStatus {
+    match player {
+        Player::X => GameStatus::XWin,
+        Player::O => GameStatus::OWin,
+    }
+}
+
+impl GameVariant for Ultimate {
+    type Move = UltimateMove;
+
+    fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    fn legal_moves(&self) -> Vec<UltimateMove> {
+        Ultimate::legal_moves(self)
+    }
+
+    // Moves that win a small board first, and moves that let the opponent play anywhere last
+    fn search_moves(&self) -> Vec<UltimateMove> {
+        let player = self.to_move;
+        let mut moves = self.legal_moves();
+        moves.sort_by_cached_key(|player_move| {
+            let mut new_game = self.clone();
+            let _ = new_game.play(player_move);
+            let wins_board =
+                new_game.board(&player_move.board).game_status() == player_won(&player);
+            (!wins_board, new_game.next_board().is_none())
+        });
+        moves
+    }
+
+    fn play(&mut self, player_move: &UltimateMove) -> Result<(), String> {
+        Ultimate::play(self, player_move)
+    }
+
+    fn is_over(&self) -> bool {
+        self.game_status != GameStatus::StillPlaying
+    }
+
+    fn status(&self) -> String {
+        format!("{:?}", self.game_status)
+    }
+
+    fn score(&self) -> i32 {
+        match self.to_move {
+            Player::X => self.evaluate(),
+            Player::O => -self.evaluate(),
+        }
+    }
+
+    // A move is "board_x board_y x y", or just "x y" when the board is forced
+    fn parse_move(&self, input: &str) -> Result<UltimateMove, String> {
+        let numbers = input
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|e| e.to_string())?;
+        match (&numbers[..], self.next_board) {
+            (&[board_x, board_y, x, y], _) => Ok(UltimateMove {
+                board: Point {
+                    x: board_x,
+                    y: board_y,
+                },
+                square: Point { x, y },
+            }),
+            (&[x, y], Some(board)) => Ok(UltimateMove {
+                board,
+                square: Point { x, y },
+            }),
+            _ => Err(format!(
+                "Incorrect number of arguments. Expected 4 (board_x board_y x y). Got {}",
+                numbers.len()
+            )),
+        }
+    }
+
+    fn note(&self) -> Option<String> {
+        Some(match self.next_board {
+            Some(board) => format!("{} plays on board {board}", self.to_move),
+            None => format!("{} plays on any open board", self.to_move),
+        })
+    }
+}
+
+#[rustfmt::skip]
+impl Ultimate {
+    const INTRO_MESSAGE: &'static str =
+        "\nWelcome to Ultimate Tic-Tac-Toe!\
+         \n--------------------------------\n\
+         \n\
+         The board is a 3x3 grid of 3x3 boards. The square you play in sends your\n\
+         opponent to the board in the same position of the grid (or anywhere, if that\n\
+         board is already won or full). Win three boards in a line to win the game.\n\
+         \n\
+         Play by entering the coordinates of the board followed by the coordinates of\n\
+         the square (e.g., \"0 2 1 1\" plays in the centre of the top right board). When\n\
+         you have to play on a particular board, the square's coordinates are enough.\n\
+         Enter \"hint\" to see the computer's suggestion.\n\
+         \n\
+         ##################################################\n";
+}
+
+impl Ultimate {
+    /// Play a game of Ultimate tic-tac-toe against the computer
+    pub fn run(config: &Config) {
+        let mut engine = Negamax::new(config.depth.unwrap_or(Self::DEFAULT_DEPTH));
+        let new_game = |first, _| Ultimate::new(first);
+        game_variant::run(config, Self::INTRO_MESSAGE, new_game, &mut engine);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_info::SquareType;
+    use crate::game_variant::VariantEngine;
+
+    fn at(board: (usize, usize), square: (usize, usize)) -> UltimateMove {
+        UltimateMove {
+            board: Point {
+                x: board.0,
+                y: board.1,
+            },
+            square: Point {
+                x: square.0,
+                y: square.1,
+            },
+        }
+    }
+
+    #[test]
+    fn moves_send_the_opponent_to_the_matching_board() {
+        let mut game = Ultimate::new(Player::X);
+        assert_eq!(game.legal_moves().len(), 81);
+        game.play(&at((1, 1), (0, 2))).unwrap();
+        assert_eq!(game.next_board(), Some(Point { x: 0, y: 2 }));
+        assert_eq!(game.legal_moves().len(), 9);
+        assert!(game.play(&at((1, 1), (0, 0))).is_err());
+        game.play(&at((0, 2), (1, 1))).unwrap();
+        assert_eq!(game.next_board(), Some(Point { x: 1, y: 1 }));
+    }
+
+    #[test]
+    fn won_boards_are_claimed_and_no_longer_played() {
+        let mut game = Ultimate::new(Player::X);
+        for y in 0..2 {
+            game.boards[0][0]
+                .insert(&Point { x: 0, y }, SquareType::X)
+                .unwrap();
+        }
+        game.next_board = Some(Point { x: 0, y: 0 });
+        game.play(&at((0, 0), (0, 2))).unwrap();
+        assert_eq!(game.big_board.square(&Point { x: 0, y: 0 }), SquareType::X);
+        assert_eq!(game.next_board(), Some(Point { x: 0, y: 2 }));
+
+        // Being sent to a decided board lets the opponent play on any open board
+        game.play(&at((0, 2), (0, 0))).unwrap();
+        assert_eq!(game.next_board(), None);
+        assert_eq!(game.legal_moves().len(), 81 - 9 - 1);
+        assert!(game.play(&at((0, 0), (2, 2))).is_err());
+    }
+
+    #[test]
+    fn engine_takes_a_winning_line_of_boards() {
+        let mut game = Ultimate::new(Player::X);
+        // X has won boards (0, 0) and (0, 1) and has two in a row on board (0, 2)
+        for (board, player) in [((0, 0), Player::X), ((0, 1), Player::X)] {
+            for y in 0..3 {
+                game.boards[board.0][board.1]
+                    .insert(&Point { x: 0, y }, player.square_type())
+                    .unwrap();
+            }
+            game.big_board
+                .insert(
+                    &Point {
+                        x: board.0,
+                        y: board.1,
+                    },
+                    SquareType::X,
+                )
+                .unwrap();
+        }
+        for y in 0..2 {
+            game.boards[0][2]
+                .insert(&Point { x: 2, y }, SquareType::X)
+                .unwrap();
+        }
+        game.next_board = Some(Point { x: 0, y: 2 });
+
+        let report = Negamax::new(2).choose_move(&game);
+        assert_eq!(report.player_move, at((0, 2), (2, 2)));
+        assert_eq!(report.player_move.to_string(), "0 2 2 2");
+        assert_eq!(report.score, Some(WIN - 1));
+
+        game.play(&report.player_move).unwrap();
+        assert_eq!(game.game_status(), GameStatus::XWin);
+    }
+
+    #[test]
+    fn evaluation_favours_the_player_with_more_boards() {
+        let mut game = Ultimate::new(Player::X);
+        assert_eq!(game.evaluate(), 0);
+        for y in 0..3 {
+            game.boards[1][1]
+                .insert(&Point { x: 0, y }, SquareType::O)
+                .unwrap();
+        }
+        game.big_board
+            .insert(&Point { x: 1, y: 1 }, SquareType::O)
+            .unwrap();
+        assert!(game.evaluate() < 0);
+    }
+}